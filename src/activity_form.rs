@@ -0,0 +1,337 @@
+use eframe::egui;
+use super::color_palette::*;
+use super::holidays::HolidayRule;
+use super::reminders::{REMINDER_CHOICES, REPEAT_CHOICES};
+use super::structs::*;
+use super::time_format::TimeFormat;
+use super::time_input::{time_picker, TimeEntry};
+
+/// Minutes from now offered as quick starts
+const QUICK_START_MINUTES: [u32; 4] = [5, 10, 15, 30];
+/// Durations offered next to the duration field, in minutes
+const DURATION_CHOICES: [u32; 5] = [15, 30, 45, 60, 90];
+
+/// Whether the form asks when an activity ends or how long it lasts
+#[derive(Clone, Copy, PartialEq)]
+pub enum Timing {
+    EndTime,
+    Duration,
+}
+
+impl Timing {
+    pub const ALL: [Timing; 2] = [Timing::EndTime, Timing::Duration];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Timing::EndTime => "End time",
+            Timing::Duration => "Duration",
+        }
+    }
+}
+
+/// Start times offered as shortcuts, in the plan's time
+pub struct QuickStarts {
+    pub now: SimpleTime,
+    /// When the activity running now, or the last one to end, ends
+    pub after_previous: Option<SimpleTime>,
+}
+
+/// Editable copy of an activity, shared by the New and Update Activity windows
+pub struct ActivityForm {
+    pub name: String,
+    pub start_time: SimpleTime,
+    pub end_time: SimpleTime,
+    pub timing: Timing,
+    /// Seconds from the start to the end, kept while the start moves in duration mode
+    pub duration: u32,
+    pub days: Days,
+    pub on_holidays: HolidayRule,
+    pub notes: String,
+    pub category: String,
+    pub silent: bool,
+    pub remind_before_start: Option<u32>,
+    pub remind_before_end: Option<u32>,
+    pub insistent: Option<Insistence>,
+}
+
+impl Default for ActivityForm {
+    fn default() -> Self {
+        ActivityForm {
+            name: "".to_string(),
+            start_time: SimpleTime::new(0, 0, 0),
+            end_time: SimpleTime::new(0, 0, 0),
+            timing: Timing::EndTime,
+            duration: 0,
+            days: Days::Every,
+            on_holidays: HolidayRule::Run,
+            notes: "".to_string(),
+            category: "".to_string(),
+            silent: false,
+            remind_before_start: None,
+            remind_before_end: None,
+            insistent: None,
+        }
+    }
+}
+
+impl ActivityForm {
+    pub fn from_activity(activity: &Activity) -> Self {
+        ActivityForm {
+            name: activity.name().to_string(),
+            start_time: *activity.start_time(),
+            end_time: *activity.end_time(),
+            timing: Timing::EndTime,
+            duration: seconds_between(activity.start_time(), activity.end_time()),
+            days: activity.days.clone(),
+            on_holidays: activity.on_holidays,
+            notes: activity.notes().to_string(),
+            category: activity.category().to_string(),
+            silent: activity.silent,
+            remind_before_start: activity.remind_before_start,
+            remind_before_end: activity.remind_before_end,
+            insistent: activity.insistent,
+        }
+    }
+
+    pub fn to_activity(&self, id: u32) -> Activity {
+        let mut activity = Activity::new(
+            id,
+            String::new(),
+            SimpleTime::new(0, 0, 0),
+            SimpleTime::new(0, 0, 0),
+        );
+        self.apply_to(&mut activity);
+        activity
+    }
+
+    pub fn apply_to(&self, activity: &mut Activity) {
+        activity.name = self.name.to_ascii_uppercase();
+        activity.start_time = self.start_time;
+        activity.end_time = self.end_time;
+        activity.notes = self.notes.trim().to_string();
+        activity.category = self.category.trim().to_lowercase();
+        activity.silent = self.silent;
+        activity.remind_before_start = self.remind_before_start;
+        activity.remind_before_end = self.remind_before_end;
+        activity.insistent = self.insistent;
+        activity.days = self.days.clone();
+        activity.on_holidays = self.on_holidays;
+    }
+
+    /// Whether the activity runs past the end of the plan day starting at `day_start`,
+    /// which the timeline and notifications can't follow
+    pub fn crosses_day_end(&self, day_start: &SimpleTime) -> bool {
+        self.start_time.seconds_into_day(day_start) + self.duration >= SECONDS_PER_DAY
+    }
+
+    /// The end `duration` after the start, going round midnight
+    fn end_after_duration(&self) -> SimpleTime {
+        SimpleTime::from_seconds((self.start_time.as_seconds() + self.duration) % SECONDS_PER_DAY)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, id_prefix: &str, format: &TimeFormat, entry: &TimeEntry, quick_starts: &QuickStarts, day_start: &SimpleTime) {
+        ui.label("Name:");
+        ui.text_edit_singleline(&mut self.name);
+
+        ui.label("Start Time:");
+        time_picker(ui, &mut self.start_time, &format!("{}_start_time", id_prefix), format, entry);
+
+        let mut quick_start = None;
+        ui.horizontal_wrapped(|ui| {
+            if ui.small_button("Now").clicked() {
+                quick_start = Some(quick_starts.now);
+            }
+            if let Some(end) = quick_starts.after_previous {
+                if ui.small_button("After previous").clicked() {
+                    quick_start = Some(end);
+                }
+            }
+            for minutes in QUICK_START_MINUTES {
+                // Starts after the day ends belong to tomorrow, which the form can't pick
+                let start = quick_starts.now.seconds_into_day(day_start) + minutes * 60;
+                let button = egui::Button::new(format!("+{}", format.duration(minutes * 60))).small();
+                if ui.add_enabled(start < SECONDS_PER_DAY, button).clicked() {
+                    quick_start = Some(SimpleTime::from_seconds((start + day_start.as_seconds()) % SECONDS_PER_DAY));
+                }
+            }
+        });
+        if let Some(start) = quick_start {
+            self.start_time = entry.snap(start);
+        }
+        // Quick starts move the end along. In duration mode the end follows any start, below.
+        if quick_start.is_some() {
+            self.end_time = self.end_after_duration();
+        }
+
+        ui.horizontal(|ui| {
+            for timing in Timing::ALL {
+                ui.radio_value(&mut self.timing, timing, timing.label());
+            }
+        });
+        match self.timing {
+            Timing::EndTime => {
+                ui.label("End Time:");
+                time_picker(ui, &mut self.end_time, &format!("{}_end_time", id_prefix), format, entry);
+                self.duration = seconds_between(&self.start_time, &self.end_time);
+                ui.label(format!("Lasts {}", format.duration(self.duration)));
+            }
+            Timing::Duration => {
+                ui.label("Duration:");
+                let mut minutes = self.duration / 60;
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut minutes).range(0..=SECONDS_PER_DAY / 60 - 1).suffix(format.minutes_suffix()));
+                    for choice in DURATION_CHOICES {
+                        if ui.small_button(format.duration(choice * 60)).clicked() {
+                            minutes = choice;
+                        }
+                    }
+                });
+                if minutes != self.duration / 60 {
+                    self.duration = minutes * 60;
+                }
+                self.end_time = self.end_after_duration();
+                ui.label(format!("Ends at {}", format.time(&self.end_time)));
+            }
+        }
+        if self.crosses_day_end(day_start) {
+            ui.label(egui::RichText::new(ends_after_day_end(day_start, format)).color(RED));
+        }
+
+        if !self.days.is_every() {
+            ui.horizontal(|ui| {
+                ui.label(format.days(&self.days));
+                if ui.small_button("Every day").clicked() {
+                    self.days = Days::Every;
+                }
+            });
+        }
+
+        egui::ComboBox::from_id_salt(format!("{}_on_holidays", id_prefix))
+            .selected_text(self.on_holidays.label())
+            .show_ui(ui, |ui| {
+                for rule in HolidayRule::ALL {
+                    ui.selectable_value(&mut self.on_holidays, rule, rule.label());
+                }
+            });
+
+        ui.label("Notes:");
+        ui.add(egui::TextEdit::multiline(&mut self.notes).desired_rows(2));
+
+        ui.label("Category:");
+        ui.text_edit_singleline(&mut self.category);
+
+        ui.label("Remind Before Start / End:");
+        ui.horizontal(|ui| {
+            activity_reminder_picker(ui, &mut self.remind_before_start, &format!("{}_remind_start", id_prefix), format);
+            activity_reminder_picker(ui, &mut self.remind_before_end, &format!("{}_remind_end", id_prefix), format);
+        });
+
+        ui.checkbox(&mut self.silent, "Silent");
+
+        let mut insistent = self.insistent.is_some();
+        if ui.checkbox(&mut insistent, "Insistent").changed() {
+            self.insistent = insistent.then(Insistence::default);
+        }
+        if let Some(insistence) = &mut self.insistent {
+            ui.horizontal(|ui| {
+                ui.label("Every");
+                egui::ComboBox::from_id_salt(format!("{}_repeat_every", id_prefix))
+                    .width(64.0)
+                    .selected_text(format.duration(insistence.every_minutes * 60))
+                    .show_ui(ui, |ui| {
+                        for choice in REPEAT_CHOICES {
+                            ui.selectable_value(&mut insistence.every_minutes, choice, format.duration(choice * 60));
+                        }
+                    });
+                ui.label("up to");
+                egui::ComboBox::from_id_salt(format!("{}_max_repeats", id_prefix))
+                    .width(40.0)
+                    .selected_text(format!("{}×", insistence.max_repeats))
+                    .show_ui(ui, |ui| {
+                        for count in 1..=10 {
+                            ui.selectable_value(&mut insistence.max_repeats, count, format!("{}×", count));
+                        }
+                    });
+            });
+        }
+    }
+}
+
+/// Seconds from `start` to `end`, going round midnight when the end comes first
+fn seconds_between(start: &SimpleTime, end: &SimpleTime) -> u32 {
+    (end.as_seconds() + SECONDS_PER_DAY - start.as_seconds()) % SECONDS_PER_DAY
+}
+
+fn reminder_label(minutes: u32, format: &TimeFormat) -> String {
+    if minutes == 0 {
+        "Off".to_string()
+    } else {
+        format.duration(minutes * 60)
+    }
+}
+
+/// Picks a lead time, where `None` follows the default from the settings
+fn activity_reminder_picker(ui: &mut egui::Ui, minutes: &mut Option<u32>, id: &str, format: &TimeFormat) {
+    egui::ComboBox::from_id_salt(id)
+        .width(64.0)
+        .selected_text(minutes.map_or("Default".to_string(), |minutes| reminder_label(minutes, format)))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(minutes, None, "Default");
+            ui.selectable_value(minutes, Some(0), "Off");
+            for choice in REMINDER_CHOICES {
+                ui.selectable_value(minutes, Some(choice), reminder_label(choice, format));
+            }
+        });
+}
+
+/// Picks a default lead time for the settings window
+pub fn reminder_picker(ui: &mut egui::Ui, minutes: &mut u32, id: &str, format: &TimeFormat) {
+    egui::ComboBox::from_id_salt(id)
+        .width(64.0)
+        .selected_text(reminder_label(*minutes, format))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(minutes, 0, "Off");
+            for choice in REMINDER_CHOICES {
+                ui.selectable_value(minutes, choice, reminder_label(choice, format));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_goes_round_midnight() {
+        assert_eq!(seconds_between(&SimpleTime::new(9, 0, 0), &SimpleTime::new(10, 30, 0)), 90 * 60);
+        assert_eq!(seconds_between(&SimpleTime::new(23, 0, 0), &SimpleTime::new(0, 30, 0)), 90 * 60);
+
+        let mut form = ActivityForm {
+            start_time: SimpleTime::new(23, 0, 0),
+            duration: 90 * 60,
+            ..Default::default()
+        };
+        assert_eq!(form.end_after_duration(), SimpleTime::new(0, 30, 0));
+        // The duration stays when the start moves back
+        form.start_time = SimpleTime::new(9, 0, 0);
+        assert_eq!(form.end_after_duration(), SimpleTime::new(10, 30, 0));
+    }
+
+    #[test]
+    fn day_end_follows_the_day_start() {
+        let form = ActivityForm {
+            start_time: SimpleTime::new(23, 0, 0),
+            duration: 2 * 3600,
+            ..Default::default()
+        };
+        assert!(form.crosses_day_end(&SimpleTime::new(0, 0, 0)));
+        assert!(!form.crosses_day_end(&SimpleTime::new(5, 0, 0)));
+        let early = ActivityForm {
+            start_time: SimpleTime::new(4, 0, 0),
+            duration: 2 * 3600,
+            ..Default::default()
+        };
+        assert!(!early.crosses_day_end(&SimpleTime::new(0, 0, 0)));
+        assert!(early.crosses_day_end(&SimpleTime::new(5, 0, 0)));
+    }
+}
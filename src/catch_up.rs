@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Utc};
+use super::clock::Clock;
+use super::history::History;
+use super::holidays::Holidays;
+use super::notifier::NotificationEvent;
+use super::structs::*;
+
+/// How far back missed transitions are looked for, however long the gap was
+pub const MAX_LOOKBACK_HOURS: i64 = 24;
+
+/// A start or end that happened while the app wasn't checking the activities
+pub struct MissedTransition {
+    pub activity_id: u32,
+    pub event: NotificationEvent,
+    pub at: DateTime<Utc>,
+}
+
+/// Every start and end that happened after `from` and up to `to`, oldest first.
+/// They happen when the clock says, like `Activity::span`: extended activities end later,
+/// and around daylight saving changes skipped activities don't happen at all.
+pub fn missed_transitions(
+    activities: &[Activity],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    clock: &dyn Clock,
+    holidays: &Holidays,
+    history: &History,
+) -> Vec<MissedTransition> {
+    // Plan days are a day at most away from the UTC date, whatever the time zone
+    let day_start = Duration::seconds(clock.day_start().as_seconds() as i64);
+    let Some(mut date) = (from - day_start).date_naive().pred_opt() else {
+        return Vec::new();
+    };
+    let last_date = (to - day_start).date_naive().succ_opt().unwrap_or(date);
+
+    let mut missed = Vec::new();
+    while date <= last_date {
+        for activity in activities.iter().filter(|activity| activity.occurs_on(date, holidays)) {
+            let Some((start, end)) = activity.span(clock, date, history) else {
+                continue;
+            };
+            for (event, at) in [(NotificationEvent::Start, start), (NotificationEvent::End, end)] {
+                if from < at && at <= to {
+                    missed.push(MissedTransition { activity_id: activity.id, event, at });
+                }
+            }
+        }
+        let Some(next_date) = date.succ_opt() else {
+            break;
+        };
+        date = next_date;
+    }
+    missed.sort_by_key(|transition| transition.at);
+    missed
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+    use super::*;
+    use super::super::time_zone::HomeClock;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn activity(id: u32, start: SimpleTime, end: SimpleTime) -> Activity {
+        Activity::new(id, format!("ACTIVITY {}", id), start, end)
+    }
+
+    fn missed(activities: &[Activity], from: DateTime<Utc>, to: DateTime<Utc>, clock: &HomeClock, history: &History) -> Vec<(u32, NotificationEvent, DateTime<Utc>)> {
+        missed_transitions(activities, from, to, clock, &Holidays::default(), history)
+            .into_iter()
+            .map(|transition| (transition.activity_id, transition.event, transition.at))
+            .collect()
+    }
+
+    #[test]
+    fn gap_across_a_start_and_an_end() {
+        let clock = HomeClock::fixed(utc(10, 19, 12, 0), chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+        let activities = [activity(0, SimpleTime::new(9, 0, 0), SimpleTime::new(10, 0, 0))];
+        assert_eq!(missed(&activities, utc(10, 19, 8, 30), utc(10, 19, 10, 30), &clock, &History::default()), vec![
+            (0, NotificationEvent::Start, utc(10, 19, 9, 0)),
+            (0, NotificationEvent::End, utc(10, 19, 10, 0)),
+        ]);
+        assert_eq!(missed(&activities, utc(10, 19, 9, 0), utc(10, 19, 9, 59), &clock, &History::default()), vec![]);
+
+        // An extended activity ends later
+        let mut history = History::default();
+        history.extend(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(), 0, 15 * 60);
+        assert_eq!(missed(&activities, utc(10, 19, 9, 30), utc(10, 19, 10, 30), &clock, &history), vec![
+            (0, NotificationEvent::End, utc(10, 19, 10, 15)),
+        ]);
+    }
+
+    #[test]
+    fn gap_across_midnight() {
+        let clock = HomeClock::fixed(utc(10, 20, 8, 0), chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+        let activities = [
+            activity(0, SimpleTime::new(23, 0, 0), SimpleTime::new(23, 30, 0)),
+            activity(1, SimpleTime::new(6, 0, 0), SimpleTime::new(7, 0, 0)),
+        ];
+        assert_eq!(missed(&activities, utc(10, 19, 22, 0), utc(10, 20, 6, 30), &clock, &History::default()), vec![
+            (0, NotificationEvent::Start, utc(10, 19, 23, 0)),
+            (0, NotificationEvent::End, utc(10, 19, 23, 30)),
+            (1, NotificationEvent::Start, utc(10, 20, 6, 0)),
+        ]);
+
+        // With the day starting at 05:00, a night shift ends on the morning after its plan day
+        let clock = HomeClock::fixed(utc(10, 20, 8, 0), chrono_tz::UTC, SimpleTime::new(5, 0, 0));
+        let night_shift = [activity(0, SimpleTime::new(23, 0, 0), SimpleTime::new(2, 0, 0))];
+        assert_eq!(missed(&night_shift, utc(10, 19, 22, 0), utc(10, 20, 3, 0), &clock, &History::default()), vec![
+            (0, NotificationEvent::Start, utc(10, 19, 23, 0)),
+            (0, NotificationEvent::End, utc(10, 20, 2, 0)),
+        ]);
+    }
+
+    #[test]
+    fn gap_across_a_daylight_saving_change() {
+        let spring = HomeClock::fixed(utc(3, 29, 6, 0), chrono_tz::Europe::Berlin, SimpleTime::new(0, 0, 0));
+        let activities = [
+            // Inside the skipped hour, so it doesn't happen
+            activity(0, SimpleTime::new(2, 0, 0), SimpleTime::new(2, 45, 0)),
+            // 01:30 CET to 03:30 CEST
+            activity(1, SimpleTime::new(1, 30, 0), SimpleTime::new(3, 30, 0)),
+        ];
+        assert_eq!(missed(&activities, utc(3, 29, 0, 0), utc(3, 29, 2, 0), &spring, &History::default()), vec![
+            (1, NotificationEvent::Start, utc(3, 29, 0, 30)),
+            (1, NotificationEvent::End, utc(3, 29, 1, 30)),
+        ]);
+
+        // In the repeated hour, only the first time round
+        let autumn = HomeClock::fixed(utc(10, 25, 6, 0), chrono_tz::Europe::Berlin, SimpleTime::new(0, 0, 0));
+        let repeated = [activity(0, SimpleTime::new(2, 15, 0), SimpleTime::new(2, 45, 0))];
+        assert_eq!(missed(&repeated, utc(10, 24, 23, 0), utc(10, 25, 3, 0), &autumn, &History::default()), vec![
+            (0, NotificationEvent::Start, utc(10, 25, 0, 15)),
+            (0, NotificationEvent::End, utc(10, 25, 0, 45)),
+        ]);
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Instant;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use super::structs::*;
+use super::time_zone;
+
+/// Speeds offered by the time simulation, in simulated seconds per real second
+pub const SIMULATION_SPEEDS: [u32; 4] = [1, 60, 600, 3600];
+
+/// When a time of the plan day starting on `date` happens: the day runs from `day_start`
+/// to the next one, so earlier times belong to the next morning
+pub fn plan_datetime(date: NaiveDate, time: &SimpleTime, day_start: &SimpleTime) -> NaiveDateTime {
+    let date = if time.as_seconds() < day_start.as_seconds() {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    };
+    date.and_time(NaiveTime::from_num_seconds_from_midnight_opt(time.as_seconds(), 0).unwrap_or_default())
+}
+
+/// Where the app gets the current time from
+pub trait Clock {
+    fn now_utc(&self) -> DateTime<Utc>;
+
+    /// Current local date and time
+    fn now(&self) -> NaiveDateTime {
+        self.now_utc().with_timezone(&Local).naive_local()
+    }
+
+    /// When a time of day happens on `date`, following the rules of `time_zone::resolve`
+    fn instant_of(&self, date: NaiveDate, time: &SimpleTime) -> DateTime<Utc> {
+        let time = NaiveTime::from_num_seconds_from_midnight_opt(time.as_seconds(), 0).unwrap_or_default();
+        time_zone::resolve(&Local, date.and_time(time))
+    }
+
+    /// When the plan's days start, midnight unless they run into the night
+    fn day_start(&self) -> SimpleTime {
+        SimpleTime::new(0, 0, 0)
+    }
+
+    /// The plan day it is now, named after the date it started on
+    fn today(&self) -> NaiveDate {
+        self.plan_date(self.now())
+    }
+
+    /// The plan day a time in the plan's time zone is on
+    fn plan_date(&self, at: NaiveDateTime) -> NaiveDate {
+        (at - Duration::seconds(self.day_start().as_seconds() as i64)).date()
+    }
+
+    /// When a time of the plan day `date` happens
+    fn plan_instant(&self, date: NaiveDate, time: &SimpleTime) -> DateTime<Utc> {
+        let at = plan_datetime(date, time, &self.day_start());
+        self.instant_of(at.date(), time)
+    }
+
+    /// How many seconds pass on this clock per real second
+    fn speed(&self) -> f64 {
+        1.0
+    }
+
+    /// Whether the time is made up, so nothing about it should be persisted
+    fn is_simulated(&self) -> bool {
+        false
+    }
+}
+
+/// The system's time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Always returns the same time, for tests
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.0
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+}
+
+/// Runs from a chosen time at a chosen speed, to watch a day go by
+pub struct SimulatedClock {
+    start: DateTime<Utc>,
+    started_at: Instant,
+    speed: u32,
+}
+
+impl SimulatedClock {
+    /// Starts at the local time `start`
+    pub fn new(start: NaiveDateTime, speed: u32) -> Self {
+        SimulatedClock {
+            start: time_zone::resolve(&Local, start),
+            started_at: Instant::now(),
+            speed,
+        }
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        let elapsed = self.started_at.elapsed().as_millis() as i64 * self.speed as i64;
+        self.start + Duration::milliseconds(elapsed)
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed as f64
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+    use super::super::time_zone::HomeClock;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn times_before_the_day_start_are_on_the_next_date() {
+        let day_start = SimpleTime::new(5, 0, 0);
+        assert_eq!(plan_datetime(date(10), &SimpleTime::new(23, 0, 0), &day_start), date(10).and_hms_opt(23, 0, 0).unwrap());
+        assert_eq!(plan_datetime(date(10), &SimpleTime::new(2, 0, 0), &day_start), date(11).and_hms_opt(2, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn today_runs_until_the_day_start() {
+        let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, 3, 11, 3, 0, 0).unwrap(), chrono_tz::UTC, SimpleTime::new(5, 0, 0));
+        assert_eq!(clock.today(), date(10));
+        assert_eq!(clock.plan_instant(date(10), &SimpleTime::new(2, 0, 0)), Utc.with_ymd_and_hms(2026, 3, 11, 2, 0, 0).unwrap());
+    }
+}
@@ -1,5 +1,10 @@
 use eframe::egui::Color32;
 
+/// The color as "#rrggbb", for SVG and CSS
+pub fn hex(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
 pub const LIGHT_GREY: Color32 = Color32::from_rgb(150, 150, 150);
 pub const GREY: Color32 = Color32::from_rgb(100, 100, 100);
 pub const DARK_GREY: Color32 = Color32::from_rgb(40, 40, 40);
@@ -11,3 +16,23 @@ pub const RED: Color32 = Color32::from_rgb(225, 100, 100);
 // pub const DARK_RED: Color32 = Color32::from_rgb(200, 80, 60);
 // pub const YELLOW: Color32 = Color32::from_rgb(225, 225, 100);
 // pub const DARK_YELLOW: Color32 = Color32::from_rgb(180, 180, 60);
+
+/// Background of activities with a category, in muted tones that keep the white names readable
+const CATEGORY_COLORS: [Color32; 6] = [
+    Color32::from_rgb(90, 120, 170),
+    Color32::from_rgb(160, 110, 70),
+    Color32::from_rgb(120, 95, 155),
+    Color32::from_rgb(70, 140, 140),
+    Color32::from_rgb(160, 95, 110),
+    Color32::from_rgb(125, 130, 70),
+];
+
+/// Background of an activity that isn't running, the same for every activity of a category
+pub fn category_color(category: &str) -> Color32 {
+    if category.is_empty() {
+        return LIGHT_GREY;
+    }
+    // Stable across runs, unlike the standard hasher
+    let hash = category.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+    CATEGORY_COLORS[hash as usize % CATEGORY_COLORS.len()]
+}
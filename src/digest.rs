@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use super::clock::Clock;
+use super::history::History;
+use super::holidays::Holidays;
+use super::notifier::Notification;
+use super::structs::*;
+use super::time_zone::LocalTimes;
+
+/// How long after its time a digest is still sent, for when the app starts late
+const DIGEST_GRACE_SECONDS: u32 = 60 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DigestKind {
+    /// Lists the day's activities
+    StartOfDay,
+    /// Sums up what was done and skipped, and what comes first tomorrow
+    EndOfDay,
+}
+
+impl DigestKind {
+    pub const ALL: [DigestKind; 2] = [DigestKind::StartOfDay, DigestKind::EndOfDay];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DigestKind::StartOfDay => "Start of day",
+            DigestKind::EndOfDay => "End of day",
+        }
+    }
+
+    /// Time offered when the digest is turned on
+    pub fn default_time(&self) -> SimpleTime {
+        match self {
+            DigestKind::StartOfDay => SimpleTime::new(8, 0, 0),
+            DigestKind::EndOfDay => SimpleTime::new(21, 0, 0),
+        }
+    }
+}
+
+/// Seconds since the digest at `time` was due, going round midnight, while it's still worth sending
+pub fn seconds_since_due(time: &SimpleTime, now: &SimpleTime) -> Option<u32> {
+    let since = (now.as_seconds() + SECONDS_PER_DAY - time.as_seconds()) % SECONDS_PER_DAY;
+    (since < DIGEST_GRACE_SECONDS).then_some(since)
+}
+
+/// Activities happening on `date`, by start time from the day start
+fn sorted<'a>(activities: &'a [Activity], date: NaiveDate, day_start: &SimpleTime, holidays: &Holidays) -> Vec<&'a Activity> {
+    let mut sorted: Vec<&Activity> = activities.iter().filter(|activity| activity.occurs_on(date, holidays)).collect();
+    sorted.sort_by_key(|activity| activity.start_time().seconds_into_day(day_start));
+    sorted
+}
+
+fn names(activities: &[&Activity]) -> String {
+    if activities.is_empty() {
+        return "none".to_string();
+    }
+    activities.iter().map(|activity| activity.name()).collect::<Vec<_>>().join(", ")
+}
+
+pub fn start_of_day(activities: &[Activity], clock: &dyn Clock, date: NaiveDate, holidays: &Holidays, local_times: &LocalTimes) -> Notification {
+    let sorted = sorted(activities, date, &clock.day_start(), holidays);
+    let mut body = if sorted.is_empty() {
+        "Nothing planned today.".to_string()
+    } else {
+        sorted
+            .iter()
+            .map(|activity| format!(
+                "{}-{} {}",
+                local_times.show(activity.start_time()),
+                local_times.show(activity.end_time()),
+                activity.name(),
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if let Some(holiday) = holidays.name(date) {
+        body = format!("{}\n{}", holiday, body);
+    }
+    Notification::new("Today's Plan", &body)
+}
+
+/// Activities that ended by now count as skipped unless marked as done on `date`
+pub fn end_of_day(
+    activities: &[Activity],
+    history: &History,
+    clock: &dyn Clock,
+    date: NaiveDate,
+    holidays: &Holidays,
+    local_times: &LocalTimes,
+) -> Notification {
+    let day_start = clock.day_start();
+    let now = clock.now_utc();
+    let sorted = sorted(activities, date, &day_start, holidays);
+    let (done, not_done): (Vec<&Activity>, Vec<&Activity>) = sorted
+        .iter()
+        .copied()
+        .partition(|activity| history.is_done(date, activity.id));
+    let skipped: Vec<&Activity> = not_done
+        .into_iter()
+        .filter(|activity| activity.span(clock, date, history).is_some_and(|(_, end)| end <= now))
+        .collect();
+
+    let mut lines = vec![
+        format!("Done: {}", names(&done)),
+        format!("Skipped: {}", names(&skipped)),
+    ];
+    let tomorrow = date.succ_opt().unwrap_or(date);
+    if let Some(first) = self::sorted(activities, tomorrow, &day_start, holidays).first() {
+        lines.push(format!("First tomorrow: {} {}", local_times.show(first.start_time()), first.name()));
+    }
+    Notification::new("Day Summary", &lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::*;
+    use super::super::time_format::{HourCycle, TimeFormat};
+    use super::super::time_zone::HomeClock;
+
+    #[test]
+    fn digests_are_due_for_the_grace_time() {
+        let morning = SimpleTime::new(8, 0, 0);
+        assert_eq!(seconds_since_due(&morning, &SimpleTime::new(7, 59, 59)), None);
+        assert_eq!(seconds_since_due(&morning, &SimpleTime::new(8, 0, 0)), Some(0));
+        assert_eq!(seconds_since_due(&morning, &SimpleTime::new(8, 59, 59)), Some(3599));
+        assert_eq!(seconds_since_due(&morning, &SimpleTime::new(9, 0, 0)), None);
+    }
+
+    #[test]
+    fn grace_time_goes_round_midnight() {
+        let late = SimpleTime::new(23, 58, 0);
+        assert_eq!(seconds_since_due(&late, &SimpleTime::new(23, 59, 0)), Some(60));
+        assert_eq!(seconds_since_due(&late, &SimpleTime::new(0, 10, 0)), Some(12 * 60));
+        assert_eq!(seconds_since_due(&late, &SimpleTime::new(0, 58, 0)), None);
+        assert_eq!(seconds_since_due(&late, &SimpleTime::new(23, 57, 59)), None);
+    }
+
+    #[test]
+    fn activities_after_midnight_end_on_the_next_date() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let activities = [
+            Activity::new(0, "NIGHT SHIFT".to_string(), SimpleTime::new(23, 0, 0), SimpleTime::new(2, 0, 0)),
+            Activity::new(1, "DINNER".to_string(), SimpleTime::new(19, 0, 0), SimpleTime::new(20, 0, 0)),
+        ];
+        let summary = |day: u32, hour: u32| {
+            let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap(), chrono_tz::UTC, SimpleTime::new(5, 0, 0));
+            let local_times = clock.local_times(TimeFormat::new(HourCycle::TwentyFourHour));
+            end_of_day(&activities, &History::default(), &clock, date, &Holidays::default(), &local_times).body
+        };
+        assert!(summary(19, 23).starts_with("Done: none\nSkipped: DINNER\n"));
+        assert!(summary(20, 3).starts_with("Done: none\nSkipped: DINNER, NIGHT SHIFT\n"));
+    }
+}
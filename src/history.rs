@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, BTreeSet};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use super::digest::DigestKind;
+use super::reminders::ReminderKind;
+
+const HISTORY_PATH: &str = "history.json";
+/// Where a history that couldn't be read is moved, so it isn't saved over
+const UNREADABLE_HISTORY_PATH: &str = "history.unreadable.json";
+/// Days kept before today, enough to catch up after a long weekend
+const KEPT_DAYS: i64 = 7;
+
+/// What happened to the activities on each day, kept across restarts
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct History {
+    /// Ids of the activities marked as done on each day
+    done: BTreeMap<NaiveDate, BTreeSet<u32>>,
+    /// Ids of the insistent activities acknowledged on each day
+    acknowledged: BTreeMap<NaiveDate, BTreeSet<u32>>,
+    /// How many times the start of each insistent activity was repeated on each day
+    repeats_sent: BTreeMap<NaiveDate, BTreeMap<u32, u32>>,
+    /// Last time the app checked the activities, to catch up after a gap
+    #[serde(rename = "last_seen_utc")]
+    last_seen: Option<DateTime<Utc>>,
+    /// Day each digest was last sent, so it goes out once a day
+    digests_sent: BTreeMap<DigestKind, NaiveDate>,
+    /// Seconds each activity was extended by on each day, past its planned end
+    extended: BTreeMap<NaiveDate, BTreeMap<u32, u32>>,
+    /// Reminders sent on each day, by activity, kind and when the event they warned of happens
+    reminders_sent: BTreeMap<NaiveDate, BTreeSet<(u32, ReminderKind, DateTime<Utc>)>>,
+}
+
+impl History {
+    pub fn load() -> std::io::Result<Self> {
+        match std::fs::read_to_string(HISTORY_PATH) {
+            Ok(history_json) => Ok(serde_json::from_str(&history_json)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(History::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn path() -> &'static str {
+        HISTORY_PATH
+    }
+
+    /// Moves the saved history out of the way, returning where it went
+    pub fn set_aside() -> std::io::Result<&'static str> {
+        std::fs::rename(HISTORY_PATH, UNREADABLE_HISTORY_PATH)?;
+        Ok(UNREADABLE_HISTORY_PATH)
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let history_json = serde_json::to_string(self)?;
+        std::fs::write(HISTORY_PATH, history_json)
+    }
+
+    pub fn is_done(&self, date: NaiveDate, activity_id: u32) -> bool {
+        self.done.get(&date).is_some_and(|ids| ids.contains(&activity_id))
+    }
+
+    pub fn set_done(&mut self, date: NaiveDate, activity_id: u32) {
+        self.done.entry(date).or_default().insert(activity_id);
+    }
+
+    pub fn is_acknowledged(&self, date: NaiveDate, activity_id: u32) -> bool {
+        self.acknowledged.get(&date).is_some_and(|ids| ids.contains(&activity_id))
+    }
+
+    pub fn set_acknowledged(&mut self, date: NaiveDate, activity_id: u32) {
+        self.acknowledged.entry(date).or_default().insert(activity_id);
+    }
+
+    pub fn repeats_sent(&self, date: NaiveDate, activity_id: u32) -> u32 {
+        self.repeats_sent
+            .get(&date)
+            .and_then(|repeats| repeats.get(&activity_id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn set_repeats_sent(&mut self, date: NaiveDate, activity_id: u32, count: u32) {
+        self.repeats_sent.entry(date).or_default().insert(activity_id, count);
+    }
+
+    pub fn extension(&self, date: NaiveDate, activity_id: u32) -> u32 {
+        self.extended
+            .get(&date)
+            .and_then(|extended| extended.get(&activity_id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Makes the activity run `seconds` longer on `date` only, on top of earlier extensions
+    pub fn extend(&mut self, date: NaiveDate, activity_id: u32, seconds: u32) {
+        *self.extended.entry(date).or_default().entry(activity_id).or_default() += seconds;
+    }
+
+    pub fn reminder_sent(&self, date: NaiveDate, activity_id: u32, kind: ReminderKind, event: DateTime<Utc>) -> bool {
+        self.reminders_sent.get(&date).is_some_and(|sent| sent.contains(&(activity_id, kind, event)))
+    }
+
+    pub fn set_reminder_sent(&mut self, date: NaiveDate, activity_id: u32, kind: ReminderKind, event: DateTime<Utc>) {
+        self.reminders_sent.entry(date).or_default().insert((activity_id, kind, event));
+    }
+
+    pub fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.last_seen
+    }
+
+    pub fn set_last_seen(&mut self, time: DateTime<Utc>) {
+        self.last_seen = Some(time);
+    }
+
+    /// Forgets the days more than `KEPT_DAYS` before `today`
+    pub fn prune(&mut self, today: NaiveDate) {
+        let oldest = today - chrono::Duration::days(KEPT_DAYS);
+        self.done.retain(|date, _| *date >= oldest);
+        self.acknowledged.retain(|date, _| *date >= oldest);
+        self.repeats_sent.retain(|date, _| *date >= oldest);
+        self.extended.retain(|date, _| *date >= oldest);
+        self.reminders_sent.retain(|date, _| *date >= oldest);
+    }
+
+    pub fn digest_sent(&self, kind: DigestKind, date: NaiveDate) -> bool {
+        self.digests_sent.get(&kind) == Some(&date)
+    }
+
+    pub fn set_digest_sent(&mut self, kind: DigestKind, date: NaiveDate) {
+        self.digests_sent.insert(kind, date);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+
+    #[test]
+    fn sent_reminders_are_kept_across_restarts() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 10, 19, 10, 0, 0).unwrap();
+        let mut history = History::default();
+        history.set_reminder_sent(date, 3, ReminderKind::BeforeEnd, end);
+
+        let history: History = serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+        assert!(history.reminder_sent(date, 3, ReminderKind::BeforeEnd, end));
+        assert!(!history.reminder_sent(date, 3, ReminderKind::BeforeStart, end));
+        // Extending the activity moves its end, which gets a reminder of its own
+        assert!(!history.reminder_sent(date, 3, ReminderKind::BeforeEnd, end + chrono::Duration::minutes(15)));
+    }
+
+    #[test]
+    fn days_past_the_kept_ones_are_forgotten() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let kept = today - chrono::Duration::days(KEPT_DAYS);
+        let forgotten = kept.pred_opt().unwrap();
+        let mut history = History::default();
+        for date in [forgotten, kept, today] {
+            history.set_done(date, 1);
+            history.set_acknowledged(date, 1);
+            history.set_repeats_sent(date, 1, 2);
+            history.extend(date, 1, 600);
+            history.set_reminder_sent(date, 1, ReminderKind::BeforeStart, Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap());
+        }
+        history.set_digest_sent(DigestKind::EndOfDay, forgotten);
+
+        history.prune(today);
+        for date in [kept, today] {
+            assert!(history.is_done(date, 1));
+            assert!(history.is_acknowledged(date, 1));
+            assert_eq!(history.repeats_sent(date, 1), 2);
+            assert_eq!(history.extension(date, 1), 600);
+        }
+        assert!(!history.is_done(forgotten, 1));
+        assert!(!history.is_acknowledged(forgotten, 1));
+        assert_eq!(history.repeats_sent(forgotten, 1), 0);
+        assert_eq!(history.extension(forgotten, 1), 0);
+        assert_eq!(history.reminders_sent.keys().copied().collect::<Vec<_>>(), vec![kept, today]);
+        // The last digest stays, however old, so it isn't sent twice
+        assert!(history.digest_sent(DigestKind::EndOfDay, forgotten));
+    }
+}
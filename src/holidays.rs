@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+/// Whether an activity happens on holidays and days off
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HolidayRule {
+    #[default]
+    Run,
+    Skip,
+    /// Only on holidays, like sleeping in
+    Only,
+}
+
+impl HolidayRule {
+    pub const ALL: [HolidayRule; 3] = [HolidayRule::Run, HolidayRule::Skip, HolidayRule::Only];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HolidayRule::Run => "Runs on holidays",
+            HolidayRule::Skip => "Skipped on holidays",
+            HolidayRule::Only => "Only on holidays",
+        }
+    }
+
+    pub fn is_run(&self) -> bool {
+        *self == HolidayRule::Run
+    }
+
+    /// Whether an activity following this rule happens on a day that is or isn't a holiday
+    pub fn allows(&self, holiday: bool) -> bool {
+        match self {
+            HolidayRule::Run => true,
+            HolidayRule::Skip => !holiday,
+            HolidayRule::Only => holiday,
+        }
+    }
+}
+
+/// Holidays read from calendar files, and the days off marked by the user
+#[derive(Default)]
+pub struct Holidays {
+    dates: BTreeMap<NaiveDate, String>,
+    /// Holidays on the same month and day every year, from a first year on
+    yearly: BTreeMap<(u32, u32), (i32, String)>,
+}
+
+impl Holidays {
+    /// Reads every calendar, skipping the ones that can't be read
+    pub fn load(calendars: &[PathBuf], days_off: &BTreeSet<NaiveDate>) -> Self {
+        let mut holidays = Holidays::default();
+        for path in calendars {
+            if let Err(error) = holidays.read(path) {
+                eprintln!("Failed to read holidays from {}: {}", path.display(), error);
+            }
+        }
+        for date in days_off {
+            holidays.dates.insert(*date, "Day off".to_string());
+        }
+        holidays
+    }
+
+    /// Reads an iCalendar file by its ".ics" extension, or a list of dates otherwise
+    fn read(&mut self, path: &Path) -> std::io::Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        let is_ics = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ics"));
+        if is_ics {
+            self.read_ics(&text);
+        } else {
+            self.read_date_list(&text);
+        }
+        Ok(())
+    }
+
+    /// Lines like "2026-12-25 Christmas", with "#" starting a comment
+    fn read_date_list(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let (date, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => {
+                    let name = if name.trim().is_empty() { "Holiday" } else { name.trim() };
+                    self.dates.insert(date, name.to_string());
+                }
+                Err(_) if line.is_empty() => {}
+                Err(_) => eprintln!("Skipped holiday line: {}", line),
+            }
+        }
+    }
+
+    /// The all-day events of an iCalendar file, skipping events at a time of day. Events repeating
+    /// on the same date every year are kept as such, other repeats are taken on their first date only.
+    fn read_ics(&mut self, text: &str) {
+        // Long lines are folded onto lines starting with a space
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.lines() {
+            match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        let (mut start, mut end, mut name, mut yearly) = (None, None, String::new(), false);
+        for line in &lines {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            // Parameters like ";VALUE=DATE" follow the property name
+            let property = key.split(';').next().unwrap_or_default().to_ascii_uppercase();
+            // Only whole dates count: events at a time of day, like "20261225T100000Z", are skipped
+            let date = || NaiveDate::parse_from_str(value.trim(), "%Y%m%d").ok();
+            match (property.as_str(), value) {
+                ("BEGIN", "VEVENT") => (start, end, name, yearly) = (None, None, String::new(), false),
+                ("DTSTART", _) => start = date(),
+                ("DTEND", _) => end = date(),
+                ("SUMMARY", _) => name = value.replace("\\,", ",").replace("\\;", ";"),
+                ("RRULE", _) => yearly = is_fixed_yearly(value),
+                ("END", "VEVENT") => {
+                    let Some(start) = start else {
+                        continue;
+                    };
+                    let name = if name.is_empty() { "Holiday".to_string() } else { name.clone() };
+                    // The end date isn't part of the event
+                    let days = end.map_or(1, |end| (end - start).num_days().max(1));
+                    for offset in 0..days {
+                        let date = start + Duration::days(offset);
+                        if yearly {
+                            self.yearly.insert((date.month(), date.day()), (date.year(), name.clone()));
+                        } else {
+                            self.dates.insert(date, name.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The holiday's name, if `date` is a holiday or a day off
+    pub fn name(&self, date: NaiveDate) -> Option<&str> {
+        if let Some(name) = self.dates.get(&date) {
+            return Some(name);
+        }
+        self.yearly
+            .get(&(date.month(), date.day()))
+            .filter(|(first_year, _)| *first_year <= date.year())
+            .map(|(_, name)| name.as_str())
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.name(date).is_some()
+    }
+}
+
+/// Whether an RRULE repeats on the same month and day every year. Rules like Thanksgiving's
+/// "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH" move from year to year, so they don't.
+fn is_fixed_yearly(rule: &str) -> bool {
+    let rule = rule.to_ascii_uppercase();
+    let parts: Vec<&str> = rule.split(';').collect();
+    parts.contains(&"FREQ=YEARLY")
+        && !parts
+            .iter()
+            .any(|part| ["BYDAY=", "BYWEEKNO=", "BYYEARDAY=", "BYSETPOS="].iter().any(|floating| part.starts_with(floating)))
+}
+
+/// Longest range of days off added at once
+const MAX_DAYS_OFF: i64 = 366;
+
+/// The days from `from` to `to`, both "YYYY-MM-DD" and included, or only `from` when `to` is empty.
+/// `None` when a date can't be read, the range runs backwards or is longer than a year.
+pub fn days_off_range(from: &str, to: &str) -> Option<Vec<NaiveDate>> {
+    let from = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d").ok()?;
+    let to = match to.trim() {
+        "" => from,
+        to => NaiveDate::parse_from_str(to, "%Y-%m-%d").ok()?,
+    };
+    let days = (to - from).num_days() + 1;
+    (1..=MAX_DAYS_OFF).contains(&days).then(|| from.iter_days().take(days as usize).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn date_lists() {
+        let mut holidays = Holidays::default();
+        holidays.read_date_list("# Office closed\n2026-12-24 Christmas Eve\n\n2026-12-31   # New Year's Eve\nnot a date\n");
+        assert_eq!(holidays.name(date(2026, 12, 24)), Some("Christmas Eve"));
+        assert_eq!(holidays.name(date(2026, 12, 31)), Some("Holiday"));
+        assert_eq!(holidays.name(date(2026, 12, 25)), None);
+    }
+
+    #[test]
+    fn all_day_events() {
+        let mut holidays = Holidays::default();
+        holidays.read_ics(
+            "BEGIN:VCALENDAR\n\
+             BEGIN:VEVENT\nDTSTART;VALUE=DATE:20261005\nSUMMARY:Company day\nEND:VEVENT\n\
+             BEGIN:VEVENT\nDTSTART;VALUE=DATE:20261228\nDTEND;VALUE=DATE:20261231\nSUMMARY:Winter\n  break\\, office closed\nEND:VEVENT\n\
+             END:VCALENDAR\n",
+        );
+        assert_eq!(holidays.name(date(2026, 10, 5)), Some("Company day"));
+        assert_eq!(holidays.name(date(2026, 10, 6)), None);
+        // Folded onto the next line, and the end date isn't part of the event
+        assert_eq!(holidays.name(date(2026, 12, 28)), Some("Winter break, office closed"));
+        assert_eq!(holidays.name(date(2026, 12, 30)), Some("Winter break, office closed"));
+        assert_eq!(holidays.name(date(2026, 12, 31)), None);
+    }
+
+    #[test]
+    fn yearly_events() {
+        let mut holidays = Holidays::default();
+        holidays.read_ics(
+            "BEGIN:VCALENDAR\n\
+             BEGIN:VEVENT\nDTSTART;VALUE=DATE:20251225\nRRULE:FREQ=YEARLY\nSUMMARY:Christmas\nEND:VEVENT\n\
+             BEGIN:VEVENT\nDTSTART;VALUE=DATE:20251127\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=4TH\nSUMMARY:Thanksgiving\nEND:VEVENT\n\
+             END:VCALENDAR\n",
+        );
+        assert_eq!(holidays.name(date(2024, 12, 25)), None);
+        assert_eq!(holidays.name(date(2025, 12, 25)), Some("Christmas"));
+        assert_eq!(holidays.name(date(2030, 12, 25)), Some("Christmas"));
+        // A weekday in November rather than a date, so only the first one is known
+        assert_eq!(holidays.name(date(2025, 11, 27)), Some("Thanksgiving"));
+        assert_eq!(holidays.name(date(2026, 11, 27)), None);
+    }
+
+    #[test]
+    fn timed_events_are_skipped() {
+        let mut holidays = Holidays::default();
+        holidays.read_ics(
+            "BEGIN:VCALENDAR\n\
+             BEGIN:VEVENT\nDTSTART;VALUE=DATE:20261225\nDTEND;VALUE=DATE:20261226\nSUMMARY:Christmas\nEND:VEVENT\n\
+             BEGIN:VEVENT\nDTSTART:20261224T100000Z\nDTEND:20261224T110000Z\nSUMMARY:Meeting\nEND:VEVENT\n\
+             BEGIN:VEVENT\nDTSTART;TZID=Europe/Berlin:20261223T090000\nSUMMARY:Dentist\nEND:VEVENT\n\
+             END:VCALENDAR\n",
+        );
+        assert_eq!(holidays.name(date(2026, 12, 25)), Some("Christmas"));
+        assert_eq!(holidays.name(date(2026, 12, 24)), None);
+        assert_eq!(holidays.name(date(2026, 12, 23)), None);
+    }
+
+    #[test]
+    fn days_off_ranges() {
+        assert_eq!(days_off_range("2026-12-24", ""), Some(vec![date(2026, 12, 24)]));
+        assert_eq!(
+            days_off_range(" 2026-12-30 ", "2027-01-01"),
+            Some(vec![date(2026, 12, 30), date(2026, 12, 31), date(2027, 1, 1)])
+        );
+        assert_eq!(days_off_range("2026-12-24", "2026-12-23"), None);
+        assert_eq!(days_off_range("2026-12-24", "2028-01-01"), None);
+        assert_eq!(days_off_range("24.12.2026", ""), None);
+        assert_eq!(days_off_range("", ""), None);
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use super::color_palette::*;
+use super::holidays::Holidays;
+use super::printable::escape_xml;
+use super::structs::*;
+use super::time_format::TimeFormat;
+
+const HOUR_HEIGHT: usize = 40;
+
+/// Keeps the current activity highlighted and the time marker in place while the page is open
+/// on the plan day it was exported for. Times are seconds since the day start, told by the clock
+/// of the plan's time zone, or the viewer's own when it has none.
+const SCRIPT: &str = r#"
+function planNow() {
+    const parts = new Intl.DateTimeFormat("en-GB", {
+        timeZone: TIME_ZONE, year: "numeric", month: "numeric", day: "numeric",
+        hour: "numeric", minute: "numeric", second: "numeric", hourCycle: "h23",
+    }).formatToParts(new Date());
+    const part = function (type) { return Number(parts.find(function (p) { return p.type === type; }).value); };
+    const seconds = part("hour") * 3600 + part("minute") * 60 + part("second");
+    // Before the day start it's still the previous plan day
+    const date = new Date(Date.UTC(part("year"), part("month") - 1, part("day") - (seconds < DAY_START ? 1 : 0)));
+    return { date: date.toISOString().slice(0, 10), seconds: (seconds - DAY_START + 86400) % 86400 };
+}
+function refresh() {
+    const now = planNow();
+    const isToday = now.date === DATE;
+    document.querySelectorAll(".activity").forEach(function (block) {
+        const start = Number(block.dataset.start);
+        const end = Number(block.dataset.end);
+        block.classList.toggle("now", isToday && start <= now.seconds && now.seconds < end);
+    });
+    const line = document.getElementById("now-line");
+    line.style.display = isToday ? "block" : "none";
+    line.style.top = (now.seconds / 86400 * 100) + "%";
+}
+refresh();
+setInterval(refresh, 10000);
+"#;
+
+/// Renders the activities of the plan day `date` as a standalone HTML page with no external resources.
+/// The page runs from the day start to the next one, with times in the plan's time zone `time_zone`,
+/// and only shows what's running while it's that plan day.
+pub fn to_html(
+    activities: &[Activity],
+    format: &TimeFormat,
+    time_zone: Option<Tz>,
+    day_start: &SimpleTime,
+    date: NaiveDate,
+    holidays: &Holidays,
+) -> String {
+    let percent = |seconds: u32| seconds as f64 / SECONDS_PER_DAY as f64 * 100.0;
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>DailyPlanner</title>\n<style>\n");
+    html.push_str(&format!(
+        "body {{ background: {}; color: {}; font-family: sans-serif; margin: 0; padding: 16px; }}\n",
+        hex(DARK_GREY), hex(WHITE),
+    ));
+    html.push_str(&format!(
+        ".timeline {{ position: relative; height: {}px; max-width: 480px; }}\n",
+        HOUR_HEIGHT * 24,
+    ));
+    html.push_str(&format!(
+        ".hour {{ position: absolute; left: 0; right: 0; height: {}px; border-top: 1px solid {}; color: {}; font-size: 14px; box-sizing: border-box; }}\n",
+        HOUR_HEIGHT, hex(GREY), hex(GREY),
+    ));
+    html.push_str(&format!(
+        ".activity {{ position: absolute; left: 64px; right: 0; color: {}; border-radius: 3px; overflow: hidden; display: flex; align-items: center; justify-content: center; font-size: 13px; box-sizing: border-box; border: 1px solid {}; }}\n",
+        hex(WHITE), hex(DARK_GREY),
+    ));
+    html.push_str(&format!(
+        ".activity.now {{ background: {}; color: {}; }}\n",
+        hex(LIGHT_GREEN), hex(DARK_GREEN),
+    ));
+    html.push_str(&format!("h1 {{ font-size: 18px; font-weight: normal; color: {}; }}\n", hex(LIGHT_GREY)));
+    html.push_str(&format!(
+        "#now-line {{ position: absolute; left: 0; right: 0; border-top: 2px solid {}; }}\n",
+        hex(RED),
+    ));
+    html.push_str("</style>\n</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_xml(&format.date(date))));
+    html.push_str("<div class=\"timeline\">\n");
+
+    // Labelled in the plan's time zone, like the activities
+    for row in 0..24 {
+        let hour = SimpleTime::from_seconds((day_start.as_seconds() + row * 3600) % SECONDS_PER_DAY);
+        html.push_str(&format!(
+            "<div class=\"hour\" style=\"top: {}px\">{}</div>\n",
+            row as usize * HOUR_HEIGHT, format.time(&hour),
+        ));
+    }
+
+    for activity in activities.iter().filter(|activity| activity.occurs_on(date, holidays)) {
+        let (start, end) = activity.day_seconds(day_start);
+        let mut tooltip = format!("{} ({} - {})", activity.name(), format.time(activity.start_time()), format.time(activity.end_time()));
+        if !activity.notes().is_empty() {
+            tooltip.push('\n');
+            tooltip.push_str(activity.notes());
+        }
+        html.push_str(&format!(
+            "<div class=\"activity\" data-start=\"{}\" data-end=\"{}\" style=\"top: {:.4}%; height: {:.4}%; background: {}\" title=\"{}\">{}</div>\n",
+            start,
+            // Activities run up to and including their last second
+            end + 1,
+            percent(start),
+            (percent(end) - percent(start)).max(0.0),
+            hex(category_color(activity.category())),
+            escape_xml(&tooltip),
+            escape_xml(activity.name()),
+        ));
+    }
+
+    html.push_str("<div id=\"now-line\"></div>\n</div>\n<script>");
+    html.push_str(&format!(
+        "\nconst TIME_ZONE = {};\nconst DAY_START = {};\nconst DATE = \"{}\";",
+        time_zone.map_or("undefined".to_string(), |time_zone| format!("\"{}\"", time_zone.name())),
+        day_start.as_seconds(),
+        date.format("%Y-%m-%d"),
+    ));
+    html.push_str(SCRIPT);
+    html.push_str("</script>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::time_format::HourCycle;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn activities_after_midnight_follow_the_evening() {
+        let night_shift = Activity::new(0, "NIGHT SHIFT".to_string(), SimpleTime::new(23, 0, 0), SimpleTime::new(2, 0, 0));
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        let html = to_html(&[night_shift], &format, None, &SimpleTime::new(5, 0, 0), date(), &Holidays::default());
+        // 18 hours after the day start, lasting 3
+        assert!(html.contains("style=\"top: 75.0000%; height: 12.5000%; background: #969696\""));
+        assert!(html.contains(&format!("data-start=\"{}\" data-end=\"{}\"", 18 * 3600, 21 * 3600 + 1)));
+        assert!(html.contains("const DAY_START = 18000;"));
+        assert!(html.contains("const TIME_ZONE = undefined;"));
+        assert!(html.contains("const DATE = \"2026-10-19\";"));
+    }
+
+    #[test]
+    fn hours_and_tooltips_are_in_the_plan_time_zone() {
+        let lunch = Activity::new(0, "LUNCH".to_string(), SimpleTime::new(12, 0, 0), SimpleTime::new(13, 0, 0));
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        let html = to_html(&[lunch], &format, Some(chrono_tz::Asia::Tokyo), &SimpleTime::new(0, 0, 0), date(), &Holidays::default());
+        assert!(html.contains("const TIME_ZONE = \"Asia/Tokyo\";"));
+        assert_eq!(html.matches("<div class=\"hour\"").count(), 24);
+        assert!(html.contains("<div class=\"hour\" style=\"top: 480px\">12:00</div>"));
+        assert!(html.contains("title=\"LUNCH (12:00 - 13:00)\""));
+    }
+
+    #[test]
+    fn activities_are_colored_by_category() {
+        let activity = |id, name: &str, category: &str| {
+            let mut activity = Activity::new(id, name.to_string(), SimpleTime::new(9 + id as u8, 0, 0), SimpleTime::new(10 + id as u8, 0, 0));
+            activity.category = category.to_string();
+            activity
+        };
+        let activities = [activity(0, "EMAIL", "work"), activity(1, "GYM", "health"), activity(2, "REVIEW", "work")];
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        let html = to_html(&activities, &format, None, &SimpleTime::new(0, 0, 0), date(), &Holidays::default());
+        let background = |name: &str| {
+            let block = html.lines().find(|line| line.ends_with(&format!(">{}</div>", name))).unwrap();
+            block.split("background: ").nth(1).unwrap().split('"').next().unwrap().to_string()
+        };
+        assert_eq!(background("EMAIL"), background("REVIEW"));
+        assert_ne!(background("EMAIL"), background("GYM"));
+        assert_eq!(background("EMAIL"), hex(category_color("work")));
+    }
+}
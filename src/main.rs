@@ -1,12 +1,36 @@
 #![windows_subsystem = "windows"]
 
 mod ui;
+mod activity_form;
+mod catch_up;
+mod clock;
 mod structs;
 mod color_palette;
+mod digest;
+mod history;
+mod holidays;
+mod html_export;
+mod notifier;
+mod org_mode;
+mod plan_format;
+mod printable;
+mod quick_add;
+mod quiet_hours;
+mod reminders;
+mod scheduler;
+mod settings;
+mod sound;
+mod status;
+mod templates;
+mod text_plan;
+mod time_format;
+mod time_input;
+mod time_zone;
+mod timeline;
 
 fn main() {
     println!("Starting UI...");
-    let app = ui::PlannerApp::new().expect("Failed to create PlannerApp");
+    let app = ui::PlannerApp::new(Box::new(clock::SystemClock)).expect("Failed to create PlannerApp");
     
     println!("Showing UI...");
     ui::show_ui(app).expect("Failed to show UI");
@@ -0,0 +1,367 @@
+use std::sync::{Arc, Mutex, mpsc::Sender};
+use std::time::{Duration, Instant};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+
+/// How long an in-app toast stays on screen unless dismissed
+const TOAST_DURATION: Duration = Duration::from_secs(10);
+
+/// What an activity notification is about
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NotificationEvent {
+    Start,
+    End,
+    Reminder,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 3] = [
+        NotificationEvent::Start,
+        NotificationEvent::End,
+        NotificationEvent::Reminder,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationEvent::Start => "Start",
+            NotificationEvent::End => "End",
+            NotificationEvent::Reminder => "Reminder",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NotificationAction {
+    Done,
+    Snooze,
+    Extend,
+    Acknowledge,
+}
+
+impl NotificationAction {
+    pub fn id(&self) -> &'static str {
+        match self {
+            NotificationAction::Done => "done",
+            NotificationAction::Snooze => "snooze",
+            NotificationAction::Extend => "extend",
+            NotificationAction::Acknowledge => "acknowledge",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::Done => "Done",
+            NotificationAction::Snooze => "Snooze 5 min",
+            NotificationAction::Extend => "Extend by 15 min",
+            NotificationAction::Acknowledge => "Acknowledge",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        [
+            NotificationAction::Done,
+            NotificationAction::Snooze,
+            NotificationAction::Extend,
+            NotificationAction::Acknowledge,
+        ]
+        .into_iter()
+        .find(|action| action.id() == id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    /// Activity the notification and its actions are about
+    pub activity_id: Option<u32>,
+    /// Plan day of the activity's run, which its actions apply to even once the next day has begun
+    pub date: Option<NaiveDate>,
+    pub event: Option<NotificationEvent>,
+    pub actions: Vec<NotificationAction>,
+}
+
+impl Notification {
+    pub fn new(summary: &str, body: &str) -> Self {
+        Notification {
+            summary: summary.to_string(),
+            body: body.to_string(),
+            activity_id: None,
+            date: None,
+            event: None,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn for_activity(mut self, activity_id: u32, date: NaiveDate, event: NotificationEvent) -> Self {
+        self.activity_id = Some(activity_id);
+        self.date = Some(date);
+        self.event = Some(event);
+        self
+    }
+
+    pub fn with_actions(mut self, actions: &[NotificationAction]) -> Self {
+        self.actions = actions.to_vec();
+        self
+    }
+}
+
+/// An action picked on a notification, routed back to `PlannerApp`
+pub struct ActionEvent {
+    pub action: NotificationAction,
+    pub notification: Notification,
+}
+
+pub trait Notifier {
+    /// Shows the notification, sent at the local time `sent_at` by the app's clock, which may be simulated.
+    /// Returns why it couldn't be shown.
+    fn notify(&mut self, notification: &Notification, sent_at: NaiveDateTime) -> Result<(), String>;
+}
+
+/// Native notifications through the system notification service.
+/// Actions are only offered where the service supports them (Linux and BSD).
+pub struct DesktopNotifier {
+    actions: Sender<ActionEvent>,
+}
+
+impl DesktopNotifier {
+    pub fn new(actions: Sender<ActionEvent>) -> Self {
+        DesktopNotifier { actions }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn wait_for_action(handle: notify_rust::NotificationHandle, notification: &Notification, actions: &Sender<ActionEvent>) {
+    if notification.actions.is_empty() {
+        return;
+    }
+    let notification = notification.clone();
+    let actions = actions.clone();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|id| {
+            if let Some(action) = NotificationAction::from_id(id) {
+                // The app may have closed in the meantime
+                let _ = actions.send(ActionEvent { action, notification });
+            }
+        });
+    });
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn wait_for_action<H>(_handle: H, _notification: &Notification, _actions: &Sender<ActionEvent>) {}
+
+impl Notifier for DesktopNotifier {
+    fn notify(&mut self, notification: &Notification, _sent_at: NaiveDateTime) -> Result<(), String> {
+        let mut desktop_notification = notify_rust::Notification::new();
+        desktop_notification
+            .appname("DailyPlanner")
+            .summary(&notification.summary)
+            .body(&notification.body)
+            .icon("icon");
+        for action in &notification.actions {
+            desktop_notification.action(action.id(), action.label());
+        }
+        let handle = desktop_notification.show().map_err(|error| error.to_string())?;
+        wait_for_action(handle, notification, &self.actions);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Toast {
+    pub notification: Notification,
+    shown_at: Instant,
+}
+
+/// Queues notifications to be drawn over the timeline by `PlannerApp`.
+/// Clones share the same queue.
+#[derive(Clone, Default)]
+pub struct ToastNotifier {
+    toasts: Arc<Mutex<Vec<Toast>>>,
+}
+
+impl ToastNotifier {
+    /// Toasts still on screen, oldest first
+    pub fn toasts(&self) -> Vec<Toast> {
+        let mut toasts = self.toasts.lock().unwrap();
+        toasts.retain(|toast| toast.shown_at.elapsed() < TOAST_DURATION);
+        toasts.clone()
+    }
+
+    /// How long until the next toast disappears, if any is on screen
+    pub fn next_expiry(&self) -> Option<Duration> {
+        self.toasts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|toast| TOAST_DURATION.saturating_sub(toast.shown_at.elapsed()))
+            .min()
+    }
+
+    pub fn dismiss(&self, index: usize) {
+        let mut toasts = self.toasts.lock().unwrap();
+        if index < toasts.len() {
+            toasts.remove(index);
+        }
+    }
+}
+
+impl Notifier for ToastNotifier {
+    fn notify(&mut self, notification: &Notification, _sent_at: NaiveDateTime) -> Result<(), String> {
+        self.toasts.lock().unwrap().push(Toast {
+            notification: notification.clone(),
+            shown_at: Instant::now(),
+        });
+        Ok(())
+    }
+}
+
+/// Prints notifications to the terminal
+pub struct LogNotifier;
+
+impl LogNotifier {
+    /// "[09:00:00] Activity Started: DEEP WORK has started.", timed by the app's clock
+    fn line(notification: &Notification, sent_at: NaiveDateTime) -> String {
+        format!("[{}] {}: {}", sent_at.format("%H:%M:%S"), notification.summary, notification.body)
+    }
+}
+
+impl Notifier for LogNotifier {
+    fn notify(&mut self, notification: &Notification, sent_at: NaiveDateTime) -> Result<(), String> {
+        println!("{}", LogNotifier::line(notification, sent_at));
+        Ok(())
+    }
+}
+
+/// Collects notifications instead of showing them, for tests
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingNotifier {
+    pub notifications: Arc<Mutex<Vec<Notification>>>,
+}
+
+#[cfg(test)]
+impl Notifier for RecordingNotifier {
+    fn notify(&mut self, notification: &Notification, _sent_at: NaiveDateTime) -> Result<(), String> {
+        self.notifications.lock().unwrap().push(notification.clone());
+        Ok(())
+    }
+}
+
+/// Tries each notifier in order until one succeeds
+pub struct FallbackNotifier {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl FallbackNotifier {
+    pub fn new(notifiers: Vec<Box<dyn Notifier>>) -> Self {
+        FallbackNotifier { notifiers }
+    }
+}
+
+impl Notifier for FallbackNotifier {
+    fn notify(&mut self, notification: &Notification, sent_at: NaiveDateTime) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for notifier in &mut self.notifiers {
+            match notifier.notify(notification, sent_at) {
+                Ok(()) => return Ok(()),
+                Err(error) => errors.push(error),
+            }
+        }
+        Err(errors.join("; "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum NotificationBackend {
+    /// Desktop notifications, falling back to in-app toasts when the service is unavailable
+    Desktop,
+    InApp,
+    Terminal,
+}
+
+impl NotificationBackend {
+    pub const ALL: [NotificationBackend; 3] = [
+        NotificationBackend::Desktop,
+        NotificationBackend::InApp,
+        NotificationBackend::Terminal,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationBackend::Desktop => "Desktop",
+            NotificationBackend::InApp => "In-app",
+            NotificationBackend::Terminal => "Terminal",
+        }
+    }
+
+    /// Builds the notifier chain for this backend, sharing the app's toast queue
+    /// and sending picked actions to `actions`
+    pub fn notifier(&self, toasts: &ToastNotifier, actions: &Sender<ActionEvent>) -> Box<dyn Notifier> {
+        match self {
+            NotificationBackend::Desktop => Box::new(FallbackNotifier::new(vec![
+                Box::new(DesktopNotifier::new(actions.clone())),
+                Box::new(toasts.clone()),
+            ])),
+            NotificationBackend::InApp => Box::new(toasts.clone()),
+            NotificationBackend::Terminal => Box::new(LogNotifier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent_at() -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(9, 0, 5).unwrap()
+    }
+
+    struct FailingNotifier(&'static str);
+
+    impl Notifier for FailingNotifier {
+        fn notify(&mut self, _notification: &Notification, _sent_at: NaiveDateTime) -> Result<(), String> {
+            Err(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn fallback_uses_the_next_notifier() {
+        let recording = RecordingNotifier::default();
+        let mut notifier = FallbackNotifier::new(vec![
+            Box::new(FailingNotifier("no service")),
+            Box::new(recording.clone()),
+        ]);
+        assert_eq!(notifier.notify(&Notification::new("Deep work", "Started"), sent_at()), Ok(()));
+        let notifications = recording.notifications.lock().unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].summary, "Deep work");
+    }
+
+    #[test]
+    fn fallback_stops_at_the_first_success() {
+        let (first, second) = (RecordingNotifier::default(), RecordingNotifier::default());
+        let mut notifier = FallbackNotifier::new(vec![Box::new(first.clone()), Box::new(second.clone())]);
+        notifier.notify(&Notification::new("Lunch", "Started"), sent_at()).unwrap();
+        assert_eq!(first.notifications.lock().unwrap().len(), 1);
+        assert!(second.notifications.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fallback_reports_every_error() {
+        let mut notifier = FallbackNotifier::new(vec![
+            Box::new(FailingNotifier("no service")),
+            Box::new(FailingNotifier("no terminal")),
+        ]);
+        assert_eq!(
+            notifier.notify(&Notification::new("Lunch", "Started"), sent_at()),
+            Err("no service; no terminal".to_string()),
+        );
+    }
+
+    #[test]
+    fn log_lines_use_the_time_sent() {
+        let notification = Notification::new("Activity Started", "DEEP WORK has started.");
+        assert_eq!(LogNotifier::line(&notification, sent_at()), "[09:00:05] Activity Started: DEEP WORK has started.");
+    }
+}
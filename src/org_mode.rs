@@ -0,0 +1,262 @@
+use chrono::{Datelike, NaiveDate};
+use super::structs::*;
+
+/// First dates from `date` on that the activity happens, with the org repeater taking it from there
+fn schedules(activity: &Activity, date: NaiveDate) -> Vec<(NaiveDate, &'static str)> {
+    match &activity.days {
+        Days::Every => vec![(date, " +1d")],
+        Days::Date(day) => vec![(*day, "")],
+        // Org has no repeater for some days of the week, so each day gets its own heading
+        Days::Weekdays(weekdays) => weekdays
+            .iter()
+            .map(|weekday| (next_date(date, *weekday), " +1w"))
+            .collect(),
+    }
+}
+
+/// Renders the plan as org-mode headings scheduled from `date` on, repeating on the activities' days
+pub fn to_org(activities: &[Activity], date: NaiveDate) -> String {
+    let mut sorted: Vec<&Activity> = activities.iter().collect();
+    sorted.sort_by_key(|activity| activity.start_time().as_seconds());
+
+    let mut org = String::from("#+TITLE: Daily Plan\n\n");
+    for activity in sorted {
+        for (scheduled, repeater) in schedules(activity, date) {
+            org.push_str(&format!("* {}\n", activity.name()));
+            org.push_str(&format!(
+                "  SCHEDULED: <{} {}-{}{}>\n",
+                scheduled.format("%Y-%m-%d %a"),
+                activity.start_time(),
+                activity.end_time(),
+                repeater,
+            ));
+            for line in activity.notes().lines() {
+                org.push_str(&format!("  {}\n", line));
+            }
+        }
+    }
+    org
+}
+
+/// Finds the start and end time in the first timed timestamp of a line, such as
+/// `<2025-01-06 Mon 09:00-10:30 +1d>` or `<2025-01-06 Mon 09:00>--<2025-01-06 Mon 10:30>`
+fn parse_time_range(line: &str) -> Option<(SimpleTime, Option<SimpleTime>)> {
+    let mut times = Vec::new();
+    for timestamp in line.split(['<', '[']).skip(1) {
+        let Some(content) = timestamp.split(['>', ']']).next() else {
+            continue;
+        };
+        for token in content.split_whitespace() {
+            match token.split_once('-') {
+                // A bad token is skipped, leaving the other times on the line
+                Some((start, end)) if start.contains(':') => {
+                    if let (Some(start), Some(end)) = (SimpleTime::parse(start), SimpleTime::parse(end)) {
+                        times.extend([start, end]);
+                    }
+                }
+                _ if token.contains(':') => times.extend(SimpleTime::parse(token)),
+                _ => {}
+            }
+        }
+    }
+    let mut times = times.into_iter();
+    Some((times.next()?, times.next()))
+}
+
+/// The title of a heading line: one or more stars and a space, unlike bold text like "*important*"
+fn heading(line: &str) -> Option<&str> {
+    let title = line.trim_start_matches('*');
+    (title.len() < line.len() && title.starts_with(' ')).then_some(title)
+}
+
+/// The days the first timestamp of a line stands for, as written by `to_org`: every day for `+1d`,
+/// its weekday for `+1w` and only its date without a repeater. Other repeaters, like `+2w` or `+1m`,
+/// can't be followed and are an error.
+fn parse_days(line: &str) -> Result<Days, String> {
+    let Some(content) = line.split(['<', '[']).nth(1).and_then(|timestamp| timestamp.split(['>', ']']).next()) else {
+        return Ok(Days::Every);
+    };
+    let Some(date) = content
+        .split_whitespace()
+        .next()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+    else {
+        return Ok(Days::Every);
+    };
+    // Repeaters are written "+1w", "++1w" or ".+1w"
+    let repeater = content.split_whitespace().find(|token| token.starts_with(['+', '.']));
+    match repeater.map(|token| token.trim_start_matches(['+', '.'])) {
+        None => Ok(Days::Date(date)),
+        Some("1d") => Ok(Days::Every),
+        Some("1w") => Ok(Days::Weekdays(vec![date.weekday()])),
+        Some(_) => Err(format!("Unsupported repeater \"{}\", only +1d and +1w are", repeater.unwrap_or_default())),
+    }
+}
+
+/// A heading that wasn't imported, and why
+#[derive(Debug, PartialEq)]
+pub struct SkippedHeading {
+    pub heading: String,
+    pub reason: String,
+}
+
+struct Entry {
+    heading: String,
+    name: String,
+    times: Option<(SimpleTime, Option<SimpleTime>)>,
+    days: Result<Days, String>,
+    notes: Vec<String>,
+}
+
+impl Entry {
+    fn into_activity(self, id: u32) -> Result<Activity, SkippedHeading> {
+        let skipped = |reason: String| SkippedHeading { heading: self.heading.clone(), reason };
+        let (start_time, end_time) = self.times.ok_or_else(|| skipped("No time of day".to_string()))?;
+        let days = self.days.clone().map_err(skipped)?;
+        let end_time = end_time.unwrap_or_else(|| {
+            SimpleTime::from_seconds(start_time.as_seconds() + DEFAULT_DURATION_SECONDS)
+        });
+        let mut activity = Activity::new(id, self.name.to_ascii_uppercase(), start_time, end_time);
+        activity.notes = self.notes.join("\n").trim().to_string();
+        activity.days = days;
+        Ok(activity)
+    }
+}
+
+/// Reads every heading with a timed SCHEDULED (or plain active) timestamp as an activity.
+/// Headings without a time of day or with a repeater that can't be followed are skipped, and
+/// returned with the reason. Weekly headings of the same activity, which `to_org` writes for each
+/// of its days, become one activity again.
+pub fn from_org(org: &str) -> (Vec<Activity>, Vec<SkippedHeading>) {
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in org.lines() {
+        let trimmed = line.trim();
+        if let Some(title) = heading(line) {
+            let title = title.trim();
+            let title = title
+                .strip_prefix("TODO ")
+                .or_else(|| title.strip_prefix("DONE "))
+                .unwrap_or(title);
+            // Drop trailing tags like ":work:"
+            let title = match title.rsplit_once(' ') {
+                Some((rest, tags)) if tags.len() > 1 && tags.starts_with(':') && tags.ends_with(':') => rest,
+                _ => title,
+            };
+            entries.push(Entry {
+                heading: line.trim().to_string(),
+                name: title.trim().to_string(),
+                times: None,
+                days: Ok(Days::Every),
+                notes: Vec::new(),
+            });
+            continue;
+        }
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+        let is_planning = ["SCHEDULED:", "DEADLINE:", "CLOSED:"].iter().any(|keyword| trimmed.starts_with(keyword));
+        if entry.times.is_none() && (trimmed.starts_with("SCHEDULED:") || trimmed.starts_with('<')) {
+            entry.times = parse_time_range(trimmed);
+            entry.days = parse_days(trimmed);
+        }
+        if !is_planning && !trimmed.starts_with(':') && !trimmed.starts_with('<') && !trimmed.starts_with("#+") {
+            entry.notes.push(trimmed.to_string());
+        }
+    }
+
+    let mut activities: Vec<Activity> = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in entries {
+        let activity = match entry.into_activity(0) {
+            Ok(activity) => activity,
+            Err(heading) => {
+                skipped.push(heading);
+                continue;
+            }
+        };
+        let same_week = activities.iter_mut().find(|other| {
+            other.name == activity.name
+                && other.start_time == activity.start_time
+                && other.end_time == activity.end_time
+                && other.notes == activity.notes
+        });
+        if let (Some(other), Days::Weekdays(weekdays)) = (same_week, &activity.days) {
+            if let Days::Weekdays(other_weekdays) = &mut other.days {
+                other_weekdays.extend(weekdays);
+                other_weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+                other_weekdays.dedup();
+                continue;
+            }
+        }
+        activities.push(activity);
+    }
+    for (id, activity) in activities.iter_mut().enumerate() {
+        activity.id = id as u32;
+    }
+    (activities, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+    use super::*;
+
+    #[test]
+    fn bold_text_is_not_a_heading() {
+        let org = "* Deep work\n  SCHEDULED: <2025-01-06 Mon 09:00-10:30>\n*important* bring notes\n";
+        let (activities, skipped) = from_org(org);
+        assert_eq!(activities.len(), 1);
+        assert!(skipped.is_empty());
+        assert_eq!(activities[0].name(), "DEEP WORK");
+        assert_eq!(activities[0].notes(), "*important* bring notes");
+    }
+
+    #[test]
+    fn days_round_trip() {
+        // 2026-10-19 is a Monday
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let mut standup = Activity::new(0, "STANDUP".to_string(), SimpleTime::new(9, 0, 0), SimpleTime::new(9, 15, 0));
+        standup.days = Days::Weekdays(vec![Weekday::Mon, Weekday::Wed]);
+        let mut dentist = Activity::new(1, "DENTIST".to_string(), SimpleTime::new(14, 0, 0), SimpleTime::new(15, 0, 0));
+        dentist.days = Days::Date(NaiveDate::from_ymd_opt(2026, 10, 22).unwrap());
+        let lunch = Activity::new(2, "LUNCH".to_string(), SimpleTime::new(12, 0, 0), SimpleTime::new(13, 0, 0));
+
+        let (activities, _) = from_org(&to_org(&[standup, dentist, lunch], date));
+        let days: Vec<(&str, &Days)> = activities.iter().map(|activity| (activity.name(), &activity.days)).collect();
+        assert_eq!(days, vec![
+            ("STANDUP", &Days::Weekdays(vec![Weekday::Mon, Weekday::Wed])),
+            ("LUNCH", &Days::Every),
+            ("DENTIST", &Days::Date(NaiveDate::from_ymd_opt(2026, 10, 22).unwrap())),
+        ]);
+        assert_eq!(activities.iter().map(|activity| activity.id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn bad_time_token_is_skipped() {
+        assert_eq!(
+            parse_time_range("<2025-01-06 Mon 25:00-26:00> <2025-01-06 Mon 09:00-10:30>"),
+            Some((SimpleTime::new(9, 0, 0), Some(SimpleTime::new(10, 30, 0)))),
+        );
+    }
+
+    #[test]
+    fn unsupported_repeaters_and_untimed_headings_are_reported() {
+        let org = "* Review :work:\n  SCHEDULED: <2026-10-19 Mon 16:00-17:00 +2w>\n\
+            * Rent\n  SCHEDULED: <2026-11-01 Sun 10:00 +1m>\n\
+            * Someday\n\
+            * TODO gym\n  SCHEDULED: <2026-10-19 Mon 18:00-19:00 ++1d>\n";
+        let (activities, skipped) = from_org(org);
+        assert_eq!(activities.iter().map(|activity| (activity.name(), &activity.days)).collect::<Vec<_>>(), vec![("GYM", &Days::Every)]);
+        assert_eq!(skipped, vec![
+            SkippedHeading {
+                heading: "* Review :work:".to_string(),
+                reason: "Unsupported repeater \"+2w\", only +1d and +1w are".to_string(),
+            },
+            SkippedHeading {
+                heading: "* Rent".to_string(),
+                reason: "Unsupported repeater \"+1m\", only +1d and +1w are".to_string(),
+            },
+            SkippedHeading { heading: "* Someday".to_string(), reason: "No time of day".to_string() },
+        ]);
+    }
+}
@@ -0,0 +1,435 @@
+use std::{collections::BTreeSet, fs, io, path::{Path, PathBuf}};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
+use super::structs::*;
+
+/// Fields worked out while the app runs, left out of the hand-editable formats
+const RUNTIME_FIELDS: [&str; 1] = ["is_now"];
+
+/// Contents of a plan file
+#[derive(Default)]
+pub struct Plan {
+    /// Time zone the activity times are in, `None` for whatever the local one is
+    pub time_zone: Option<Tz>,
+    pub activities: Vec<Activity>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PlanFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl PlanFormat {
+    pub const ALL: [PlanFormat; 3] = [PlanFormat::Json, PlanFormat::Toml, PlanFormat::Yaml];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanFormat::Json => "JSON",
+            PlanFormat::Toml => "TOML",
+            PlanFormat::Yaml => "YAML",
+        }
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            PlanFormat::Json => &["json"],
+            PlanFormat::Toml => &["toml"],
+            PlanFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        PlanFormat::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// File written by "Save Plan" and loaded on startup
+    pub fn plan_path(&self) -> PathBuf {
+        PathBuf::from(format!("plan.{}", self.extensions()[0]))
+    }
+}
+
+fn invalid_data(error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Replaces {hour, minute, seconds} objects by "09:00" strings and drops nulls
+fn humanize(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            if let Ok(time) = serde_json::from_value::<SimpleTime>(Value::Object(fields.clone())) {
+                return Value::String(time.to_string());
+            }
+            Value::Object(
+                fields
+                    .into_iter()
+                    .filter(|(_, field)| !field.is_null())
+                    .map(|(key, field)| (key, humanize(field)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(humanize).collect()),
+        value => value,
+    }
+}
+
+/// Reverts `humanize`, parsing the strings of every `*_time` field
+fn dehumanize(value: Value) -> io::Result<Value> {
+    match value {
+        Value::Object(fields) => {
+            let mut result = Map::new();
+            for (key, field) in fields {
+                let field = match field {
+                    Value::String(text) if key.ends_with("_time") => {
+                        let time = SimpleTime::parse(&text)
+                            .ok_or_else(|| invalid_data(format!("Invalid time \"{}\" for {}", text, key)))?;
+                        serde_json::to_value(time).map_err(invalid_data)?
+                    }
+                    field => dehumanize(field)?,
+                };
+                result.insert(key, field);
+            }
+            Ok(Value::Object(result))
+        }
+        Value::Array(items) => Ok(Value::Array(items.into_iter().map(dehumanize).collect::<io::Result<_>>()?)),
+        value => Ok(value),
+    }
+}
+
+fn parse_time_zone(value: Option<Value>) -> io::Result<Option<Tz>> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(name)) => name
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid_data(format!("Unknown time zone \"{}\"", name))),
+        Some(_) => Err(invalid_data("Expected a time zone name like \"Europe/Paris\"")),
+    }
+}
+
+fn to_human_value(activities: &[Activity], time_zone: Option<Tz>) -> io::Result<Value> {
+    let mut entries = Vec::new();
+    for activity in activities {
+        let mut entry = humanize(serde_json::to_value(activity).map_err(invalid_data)?);
+        if let Value::Object(fields) = &mut entry {
+            for field in RUNTIME_FIELDS {
+                fields.shift_remove(field);
+            }
+        }
+        entries.push(entry);
+    }
+    let mut plan = Map::new();
+    if let Some(time_zone) = time_zone {
+        plan.insert("time_zone".to_string(), Value::String(time_zone.name().to_string()));
+    }
+    plan.insert("activities".to_string(), Value::Array(entries));
+    Ok(Value::Object(plan))
+}
+
+fn from_human_value(value: Value) -> io::Result<Plan> {
+    let (time_zone, entries) = match value {
+        Value::Null => return Ok(Plan::default()),
+        Value::Object(mut plan) => (
+            parse_time_zone(plan.shift_remove("time_zone"))?,
+            plan.shift_remove("activities").unwrap_or(Value::Array(Vec::new())),
+        ),
+        _ => return Err(invalid_data("Expected a table with an \"activities\" list")),
+    };
+    Ok(Plan { time_zone, activities: activities_from(dehumanize(entries)?)? })
+}
+
+/// Reads the list of activities, giving those without an id of their own a new one
+fn activities_from(entries: Value) -> io::Result<Vec<Activity>> {
+    let has_id: Vec<bool> = match &entries {
+        Value::Array(items) => items.iter().map(|item| item.get("id").is_some()).collect(),
+        _ => Vec::new(),
+    };
+    let mut activities: Vec<Activity> = serde_json::from_value(entries).map_err(invalid_data)?;
+    assign_missing_ids(&mut activities, &has_id);
+    Ok(activities)
+}
+
+/// Gives new ids to activities added by hand without one, or copied along with another's,
+/// keeping the ids the history refers to
+fn assign_missing_ids(activities: &mut [Activity], has_id: &[bool]) {
+    let mut next_id = activities
+        .iter()
+        .zip(has_id)
+        .filter(|(_, has_id)| **has_id)
+        .map(|(activity, _)| activity.id + 1)
+        .max()
+        .unwrap_or(0);
+    let mut seen = BTreeSet::new();
+    for (activity, has_id) in activities.iter_mut().zip(has_id) {
+        if !*has_id || !seen.insert(activity.id) {
+            activity.id = next_id;
+            seen.insert(next_id);
+            next_id += 1;
+        }
+    }
+}
+
+/// JSON plans are a plain list of activities, or an object with the time zone when there is one
+fn from_json_value(value: Value) -> io::Result<Plan> {
+    match value {
+        Value::Object(mut plan) => Ok(Plan {
+            time_zone: parse_time_zone(plan.shift_remove("time_zone"))?,
+            activities: activities_from(plan.shift_remove("activities").unwrap_or(Value::Array(Vec::new())))?,
+        }),
+        value => Ok(Plan {
+            time_zone: None,
+            activities: activities_from(value)?,
+        }),
+    }
+}
+
+fn to_json_value(activities: &[Activity], time_zone: Option<Tz>) -> io::Result<Value> {
+    let activities = serde_json::to_value(activities).map_err(invalid_data)?;
+    let Some(time_zone) = time_zone else {
+        return Ok(activities);
+    };
+    let mut plan = Map::new();
+    plan.insert("time_zone".to_string(), Value::String(time_zone.name().to_string()));
+    plan.insert("activities".to_string(), activities);
+    Ok(Value::Object(plan))
+}
+
+/// Updates the values of `table` in place so comments and key order survive
+fn merge_toml_table(table: &mut Table, updated: Table) {
+    let stale_keys: Vec<String> = table
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !updated.contains_key(key))
+        .collect();
+    for key in stale_keys {
+        table.remove(&key);
+    }
+
+    for (key, item) in updated {
+        match table.get_mut(&key) {
+            Some(existing) => {
+                let decor = existing.as_value().map(|value| value.decor().clone());
+                *existing = item;
+                if let (Some(decor), Some(value)) = (decor, existing.as_value_mut()) {
+                    *value.decor_mut() = decor;
+                }
+            }
+            None => {
+                table.insert(&key, item);
+            }
+        }
+    }
+}
+
+/// Whether `existing` is the table `updated` was saved from: the one with the same id,
+/// or for tables written before ids were kept, the one with the same name and start time
+fn same_activity(existing: &Table, updated: &Table) -> bool {
+    match existing.get("id") {
+        Some(id) => id.as_integer() == updated.get("id").and_then(Item::as_integer),
+        None => ["name", "start_time"]
+            .iter()
+            .all(|key| existing.get(key).and_then(Item::as_str) == updated.get(key).and_then(Item::as_str)),
+    }
+}
+
+/// Numbers `table` and the tables nested in it from `next` on, so they're written in that order
+fn set_positions(table: &mut Table, next: &mut usize) {
+    table.set_position(*next);
+    *next += 1;
+    for (_, item) in table.iter_mut() {
+        if let Some(nested) = item.as_table_mut() {
+            set_positions(nested, next);
+        }
+    }
+}
+
+/// Applies a freshly serialized plan over the existing file, keeping its comments.
+/// Activities are matched by id, so comments stay with their activity when others are
+/// removed or the plan is reordered.
+fn merge_toml(existing: &str, updated: &str) -> String {
+    let (Ok(mut document), Ok(updated_document)) = (existing.parse::<DocumentMut>(), updated.parse::<DocumentMut>()) else {
+        return updated.to_string();
+    };
+    let updated_tables: Vec<Table> = updated_document
+        .get("activities")
+        .and_then(Item::as_array_of_tables)
+        .map(|tables| tables.iter().cloned().collect())
+        .unwrap_or_default();
+
+    match updated_document.get("time_zone") {
+        Some(time_zone) => document["time_zone"] = time_zone.clone(),
+        None => {
+            document.remove("time_zone");
+        }
+    }
+
+    let Some(existing_tables) = document.get_mut("activities").and_then(Item::as_array_of_tables_mut) else {
+        document["activities"] = updated_document["activities"].clone();
+        return document.to_string();
+    };
+    let mut next_position = existing_tables.iter().filter_map(Table::position).min().unwrap_or(0);
+    let mut unmatched: Vec<Option<Table>> = existing_tables.iter().cloned().map(Some).collect();
+    let mut tables = ArrayOfTables::new();
+    for updated_table in updated_tables {
+        let existing = unmatched
+            .iter_mut()
+            .find(|table| table.as_ref().is_some_and(|table| same_activity(table, &updated_table)))
+            .and_then(Option::take);
+        let mut table = match existing {
+            Some(mut table) => {
+                merge_toml_table(&mut table, updated_table);
+                table
+            }
+            None => updated_table,
+        };
+        set_positions(&mut table, &mut next_position);
+        tables.push(table);
+    }
+    document["activities"] = Item::ArrayOfTables(tables);
+    document.to_string()
+}
+
+/// Loads a plan, picking the format from the file extension
+pub fn load_plan(path: &Path) -> io::Result<Plan> {
+    let contents = fs::read_to_string(path)?;
+    match PlanFormat::from_path(path).unwrap_or(PlanFormat::Json) {
+        PlanFormat::Json => from_json_value(serde_json::from_str(&contents).map_err(invalid_data)?),
+        PlanFormat::Toml => from_human_value(toml::from_str(&contents).map_err(invalid_data)?),
+        PlanFormat::Yaml => from_human_value(serde_yaml_ng::from_str(&contents).map_err(invalid_data)?),
+    }
+}
+
+/// Saves a plan, picking the format from the file extension.
+/// Comments in an existing TOML file are kept; YAML and JSON are rewritten.
+pub fn save_plan(path: &Path, activities: &[Activity], time_zone: Option<Tz>, pretty_json: bool) -> io::Result<()> {
+    let contents = match PlanFormat::from_path(path).unwrap_or(PlanFormat::Json) {
+        PlanFormat::Json if pretty_json => serde_json::to_string_pretty(&to_json_value(activities, time_zone)?)?,
+        PlanFormat::Json => serde_json::to_string(&to_json_value(activities, time_zone)?)?,
+        PlanFormat::Toml => {
+            let updated = toml::to_string(&to_human_value(activities, time_zone)?).map_err(invalid_data)?;
+            match fs::read_to_string(path) {
+                Ok(existing) => merge_toml(&existing, &updated),
+                Err(_) => updated,
+            }
+        }
+        PlanFormat::Yaml => serde_yaml_ng::to_string(&to_human_value(activities, time_zone)?).map_err(invalid_data)?,
+    };
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(id: u32, name: &str, start_hour: u8, end_hour: u8) -> Activity {
+        Activity::new(id, name.to_string(), SimpleTime::new(start_hour, 0, 0), SimpleTime::new(end_hour, 0, 0))
+    }
+
+    fn save(existing: &str, activities: &[Activity]) -> String {
+        let updated = toml::to_string(&to_human_value(activities, None).unwrap()).unwrap();
+        merge_toml(existing, &updated)
+    }
+
+    fn load(toml: &str) -> Vec<Activity> {
+        from_human_value(toml::from_str(toml).unwrap()).unwrap().activities
+    }
+
+    fn names(activities: &[Activity]) -> Vec<(u32, &str)> {
+        activities.iter().map(|activity| (activity.id, activity.name())).collect()
+    }
+
+    /// A plan saved once and then commented by hand
+    fn commented_plan() -> String {
+        let plan = save("", &[activity(0, "DEEP WORK", 9, 11), activity(1, "LUNCH", 12, 13), activity(2, "GYM", 18, 19)]);
+        plan.replacen("[[activities]]", "# morning block\n[[activities]]", 1)
+            .replacen("name = \"LUNCH\"", "name = \"LUNCH\" # with the team", 1)
+            .replacen("[[activities]]\nid = 1", "# lunch\n[[activities]]\nid = 1", 1)
+    }
+
+    #[test]
+    fn deleting_an_activity_drops_only_its_comments() {
+        let plan = commented_plan();
+        assert!(plan.contains("# lunch"));
+        let activities = load(&plan);
+        let saved = save(&plan, &activities[1..]);
+        assert!(!saved.contains("# morning block"));
+        assert!(saved.contains("# lunch\n[[activities]]\nid = 1"));
+        assert!(saved.contains("name = \"LUNCH\" # with the team"));
+        assert_eq!(names(&load(&saved)), vec![(1, "LUNCH"), (2, "GYM")]);
+    }
+
+    #[test]
+    fn reordering_keeps_comments_with_their_activity() {
+        let plan = commented_plan();
+        let mut activities = load(&plan);
+        activities.reverse();
+        let saved = save(&plan, &activities);
+        assert!(saved.contains("# morning block\n[[activities]]\nid = 0"));
+        assert!(saved.contains("# lunch\n[[activities]]\nid = 1"));
+        assert!(saved.find("GYM").unwrap() < saved.find("LUNCH").unwrap());
+        assert!(saved.find("LUNCH").unwrap() < saved.find("DEEP WORK").unwrap());
+        assert_eq!(names(&load(&saved)), vec![(2, "GYM"), (1, "LUNCH"), (0, "DEEP WORK")]);
+    }
+
+    #[test]
+    fn editing_keeps_comments() {
+        let plan = commented_plan();
+        let mut activities = load(&plan);
+        activities[1].name = "LATE LUNCH".to_string();
+        activities[1].end_time = SimpleTime::new(14, 0, 0);
+        activities[1].insistent = Some(Insistence::default());
+        let saved = save(&plan, &activities);
+        assert!(saved.contains("# morning block\n[[activities]]\nid = 0"));
+        assert!(saved.contains("# lunch\n[[activities]]\nid = 1"));
+        assert!(saved.contains("name = \"LATE LUNCH\" # with the team"));
+        assert!(saved.contains("end_time = \"14:00\""));
+        let reloaded = load(&saved);
+        assert_eq!(names(&reloaded), vec![(0, "DEEP WORK"), (1, "LATE LUNCH"), (2, "GYM")]);
+        assert_eq!(reloaded[1].insistent, Some(Insistence::default()));
+    }
+
+    #[test]
+    fn ids_survive_reordering_by_hand() {
+        let plan = save("", &[activity(4, "DEEP WORK", 9, 11), activity(7, "LUNCH", 12, 13)]);
+        let (header, entries) = plan.split_once("[[activities]]").unwrap();
+        let (deep_work, lunch) = entries.split_once("[[activities]]").unwrap();
+        let reordered = format!("{}[[activities]]{}[[activities]]{}", header, lunch, deep_work);
+        assert_eq!(names(&load(&reordered)), vec![(7, "LUNCH"), (4, "DEEP WORK")]);
+    }
+
+    #[test]
+    fn activities_added_by_hand_get_new_ids() {
+        let plan = "[[activities]]\nid = 3\nname = \"DEEP WORK\"\nstart_time = \"09:00\"\nend_time = \"11:00\"\n\n\
+            [[activities]]\nname = \"NAP\"\nstart_time = \"14:00\"\nend_time = \"14:30\"\n\n\
+            [[activities]]\nid = 3\nname = \"READING\"\nstart_time = \"21:00\"\nend_time = \"22:00\"\n";
+        assert_eq!(names(&load(plan)), vec![(3, "DEEP WORK"), (4, "NAP"), (5, "READING")]);
+    }
+
+    #[test]
+    fn json_activities_added_by_hand_get_new_ids() {
+        let plan = r#"[
+            {"id": 3, "name": "DEEP WORK", "start_time": {"hour": 9, "minute": 0, "seconds": 0}, "end_time": {"hour": 11, "minute": 0, "seconds": 0}},
+            {"name": "NAP", "start_time": {"hour": 14, "minute": 0, "seconds": 0}, "end_time": {"hour": 14, "minute": 30, "seconds": 0}},
+            {"id": 3, "name": "READING", "start_time": {"hour": 21, "minute": 0, "seconds": 0}, "end_time": {"hour": 22, "minute": 0, "seconds": 0}}
+        ]"#;
+        let activities = from_json_value(serde_json::from_str(plan).unwrap()).unwrap().activities;
+        assert_eq!(names(&activities), vec![(3, "DEEP WORK"), (4, "NAP"), (5, "READING")]);
+    }
+
+    #[test]
+    fn plans_without_ids_keep_comments_by_name_and_start() {
+        let plan = "# morning block\n[[activities]]\nname = \"DEEP WORK\"\nstart_time = \"09:00\"\nend_time = \"11:00\"\n\n\
+            # lunch\n[[activities]]\nname = \"LUNCH\"\nstart_time = \"12:00\"\nend_time = \"13:00\"\n";
+        let activities = load(plan);
+        let saved = save(plan, &activities[1..]);
+        assert!(!saved.contains("# morning block"));
+        assert!(saved.contains("# lunch\n[[activities]]"));
+        assert_eq!(names(&load(&saved)), vec![(1, "LUNCH")]);
+    }
+}
@@ -0,0 +1,497 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use eframe::egui::Color32;
+use super::color_palette::*;
+use super::holidays::Holidays;
+use super::structs::*;
+use super::time_format::TimeFormat;
+
+const MARGIN: f32 = 36.0;
+const TITLE_HEIGHT: f32 = 30.0;
+const HEADER_HEIGHT: f32 = 18.0;
+const HOUR_LABEL_WIDTH: f32 = 40.0;
+const LABEL_SIZE: f32 = 9.0;
+/// Average Helvetica glyph width, as a share of the font size
+const GLYPH_WIDTH: f32 = 0.55;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PaperSize {
+    A4,
+    Letter,
+}
+
+impl PaperSize {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::Letter => "Letter",
+        }
+    }
+
+    /// Portrait width and height in points (1/72 inch)
+    fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (595.28, 841.89),
+            PaperSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "Portrait",
+            Orientation::Landscape => "Landscape",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PrintRange {
+    Day,
+    Week,
+}
+
+impl PrintRange {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrintRange::Day => "Day",
+            PrintRange::Week => "Week",
+        }
+    }
+}
+
+pub struct PrintOptions {
+    pub range: PrintRange,
+    pub paper_size: PaperSize,
+    pub orientation: Orientation,
+    /// First hour shown on the grid, inclusive, in hours from the day start
+    pub first_hour: u8,
+    /// Hour where the grid ends, in hours from the day start, 24 meaning the next day start
+    pub last_hour: u8,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            range: PrintRange::Week,
+            paper_size: PaperSize::A4,
+            orientation: Orientation::Landscape,
+            first_hour: 0,
+            last_hour: 24,
+        }
+    }
+}
+
+impl PrintOptions {
+    fn page_size(&self) -> (f32, f32) {
+        let (width, height) = self.paper_size.dimensions();
+        match self.orientation {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Page elements in points, with the origin at the top left corner
+enum Shape {
+    Rect { x: f32, y: f32, width: f32, height: f32, fill: Color32 },
+    Line { x1: f32, y1: f32, x2: f32, y2: f32, width: f32, stroke: Color32 },
+    Text { x: f32, y: f32, size: f32, text: String, color: Color32, anchor: Anchor },
+}
+
+/// The time of day `hour` hours after the day start, keeping 24 for the midnight that ends a day starting at midnight
+pub fn hour_of_day(day_start: u8, hour: u32) -> u32 {
+    match (day_start as u32 + hour) % 24 {
+        0 if hour == 24 => 24,
+        hour => hour,
+    }
+}
+
+/// Cuts the text so it fits in the given width
+fn fit_text(text: &str, size: f32, width: f32) -> String {
+    let max_chars = (width / (size * GLYPH_WIDTH)).floor().max(0.0) as usize;
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else if max_chars <= 1 {
+        String::new()
+    } else {
+        let mut fitted: String = text.chars().take(max_chars - 1).collect();
+        fitted.push('…');
+        fitted
+    }
+}
+
+/// Lays out the plan for `date`, or for the week from Monday to Sunday around it.
+/// Each column runs from `day_start` to the next one, so activities after midnight follow the evening.
+fn layout(
+    activities: &[Activity],
+    options: &PrintOptions,
+    format: &TimeFormat,
+    date: NaiveDate,
+    day_start: &SimpleTime,
+    holidays: &Holidays,
+    win_ansi: bool,
+) -> ((f32, f32), Vec<Shape>) {
+    let (page_width, page_height) = options.page_size();
+    let first_hour = options.first_hour.min(23) as u32;
+    let last_hour = (options.last_hour as u32).clamp(first_hour + 1, 24);
+    let columns = match options.range {
+        PrintRange::Day => 1,
+        PrintRange::Week => 7,
+    };
+
+    let grid_x = MARGIN + HOUR_LABEL_WIDTH;
+    let grid_y = MARGIN + TITLE_HEIGHT + HEADER_HEIGHT;
+    let grid_width = page_width - MARGIN - grid_x;
+    let grid_height = page_height - MARGIN - grid_y;
+    let column_width = grid_width / columns as f32;
+    let hour_height = grid_height / (last_hour - first_hour) as f32;
+    let range_start = first_hour * 3600;
+    let range_end = last_hour * 3600;
+    let y_for = |seconds: u32| grid_y + (seconds - range_start) as f32 / 3600.0 * hour_height;
+
+    let mut shapes = Vec::new();
+
+    let title = match options.range {
+        PrintRange::Day => "Daily Plan",
+        PrintRange::Week => "Weekly Plan",
+    };
+    shapes.push(Shape::Text {
+        x: MARGIN,
+        y: MARGIN + 18.0,
+        size: 18.0,
+        text: title.to_string(),
+        color: DARK_GREY,
+        anchor: Anchor::Start,
+    });
+
+    // Column headers
+    if options.range == PrintRange::Week {
+        for (column, day) in WORKDAYS.iter().chain(WEEKEND.iter()).enumerate() {
+            shapes.push(Shape::Text {
+                x: grid_x + column_width * (column as f32 + 0.5),
+                y: grid_y - 5.0,
+                size: 10.0,
+                text: fit_text(&weekday_name(format.weekday(*day), *day, win_ansi), 10.0, column_width),
+                color: DARK_GREY,
+                anchor: Anchor::Middle,
+            });
+        }
+    }
+
+    // Activity blocks go under the gridlines so the hours stay readable
+    let first_date = match options.range {
+        PrintRange::Day => date,
+        PrintRange::Week => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+    };
+    for column in 0..columns {
+        let column_date = first_date + chrono::Duration::days(column as i64);
+        let column_x = grid_x + column_width * column as f32;
+        for activity in activities.iter().filter(|activity| activity.occurs_on(column_date, holidays)) {
+            let (start, end) = activity.day_seconds(day_start);
+            let (start, end) = (start.max(range_start), end.min(range_end));
+            if start >= end {
+                continue;
+            }
+
+            let x = column_x + 1.5;
+            let y = y_for(start);
+            let width = column_width - 3.0;
+            let height = y_for(end) - y;
+            shapes.push(Shape::Rect { x, y, width, height, fill: LIGHT_GREY });
+
+            if height >= LABEL_SIZE + 3.0 {
+                shapes.push(Shape::Text {
+                    x: x + 3.0,
+                    y: y + LABEL_SIZE + 1.0,
+                    size: LABEL_SIZE,
+                    text: fit_text(activity.name(), LABEL_SIZE, width - 6.0),
+                    color: DARK_GREY,
+                    anchor: Anchor::Start,
+                });
+            }
+            if height >= LABEL_SIZE * 2.0 + 6.0 {
+                let range = format!(
+                    "{}-{}",
+                    format.time(activity.start_time()),
+                    format.time(activity.end_time()),
+                );
+                shapes.push(Shape::Text {
+                    x: x + 3.0,
+                    y: y + LABEL_SIZE * 2.0 + 3.0,
+                    size: LABEL_SIZE - 1.0,
+                    text: fit_text(&range, LABEL_SIZE - 1.0, width - 6.0),
+                    color: GREY,
+                    anchor: Anchor::Start,
+                });
+            }
+        }
+    }
+
+    // Hour gridlines and labels
+    for hour in first_hour..=last_hour {
+        let y = y_for(hour * 3600);
+        shapes.push(Shape::Line {
+            x1: grid_x,
+            y1: y,
+            x2: grid_x + grid_width,
+            y2: y,
+            width: 0.5,
+            stroke: GREY,
+        });
+        shapes.push(Shape::Text {
+            x: grid_x - 4.0,
+            y: y + 3.0,
+            size: 8.0,
+            text: format.hour(hour_of_day(day_start.hour(), hour)),
+            color: GREY,
+            anchor: Anchor::End,
+        });
+    }
+
+    // Column separators
+    for column in 0..=columns {
+        let x = grid_x + column_width * column as f32;
+        shapes.push(Shape::Line {
+            x1: x,
+            y1: grid_y,
+            x2: x,
+            y2: grid_y + grid_height,
+            width: 0.5,
+            stroke: GREY,
+        });
+    }
+
+    ((page_width, page_height), shapes)
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the plan as an SVG document sized to the selected paper
+pub fn to_svg(
+    activities: &[Activity],
+    options: &PrintOptions,
+    format: &TimeFormat,
+    date: NaiveDate,
+    day_start: &SimpleTime,
+    holidays: &Holidays,
+) -> String {
+    let ((width, height), shapes) = layout(activities, options, format, date, day_start, holidays, false);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.2}pt\" height=\"{h:.2}pt\" viewBox=\"0 0 {w:.2} {h:.2}\" font-family=\"Helvetica, Arial, sans-serif\">\n",
+        w = width,
+        h = height,
+    );
+    svg.push_str(&format!("<rect width=\"{:.2}\" height=\"{:.2}\" fill=\"#ffffff\"/>\n", width, height));
+    for shape in shapes {
+        match shape {
+            Shape::Rect { x, y, width, height, fill } => svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" rx=\"2\" fill=\"{}\"/>\n",
+                x, y, width, height, hex(fill),
+            )),
+            Shape::Line { x1, y1, x2, y2, width, stroke } => svg.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke-width=\"{:.2}\" stroke=\"{}\"/>\n",
+                x1, y1, x2, y2, width, hex(stroke),
+            )),
+            Shape::Text { x, y, size, text, color, anchor } => {
+                let anchor = match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                    Anchor::End => "end",
+                };
+                svg.push_str(&format!(
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{:.2}\" text-anchor=\"{}\" fill=\"{}\">{}</text>\n",
+                    x, y, size, anchor, hex(color), escape_xml(&text),
+                ));
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn pdf_color(color: Color32) -> String {
+    format!(
+        "{:.3} {:.3} {:.3}",
+        color.r() as f32 / 255.0,
+        color.g() as f32 / 255.0,
+        color.b() as f32 / 255.0,
+    )
+}
+
+/// The byte for a character in WinAnsi, for the ones the PDF font can show
+fn win_ansi_byte(c: char) -> Option<u8> {
+    match c {
+        '…' => Some(0x85),
+        c if (0x20..0x7f).contains(&(c as u32)) || (0xa0..=0xff).contains(&(c as u32)) => Some(c as u32 as u8),
+        _ => None,
+    }
+}
+
+/// The weekday name as the locale writes it, or in English when the PDF font can't show it,
+/// like Japanese or Russian names
+fn weekday_name(localized: String, weekday: Weekday, win_ansi: bool) -> String {
+    if !win_ansi || localized.chars().all(|c| win_ansi_byte(c).is_some()) {
+        return localized;
+    }
+    // Any week will do
+    NaiveDate::from_isoywd_opt(2024, 1, weekday).unwrap_or_default().format("%A").to_string()
+}
+
+/// Encodes text as a PDF string literal in WinAnsi, replacing what it can't represent
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                bytes.push(b'\\');
+                bytes.push(c as u8);
+            }
+            c if (c as u32) < 0x20 => bytes.push(b' '),
+            c => bytes.push(win_ansi_byte(c).unwrap_or(b'?')),
+        }
+    }
+    bytes.push(b')');
+    bytes
+}
+
+/// Renders the plan as a single page PDF using the built-in Helvetica font
+pub fn to_pdf(
+    activities: &[Activity],
+    options: &PrintOptions,
+    format: &TimeFormat,
+    date: NaiveDate,
+    day_start: &SimpleTime,
+    holidays: &Holidays,
+) -> Vec<u8> {
+    let ((page_width, page_height), shapes) = layout(activities, options, format, date, day_start, holidays, true);
+
+    let mut content: Vec<u8> = Vec::new();
+    for shape in shapes {
+        match shape {
+            Shape::Rect { x, y, width, height, fill } => content.extend(format!(
+                "{} rg {:.2} {:.2} {:.2} {:.2} re f\n",
+                pdf_color(fill), x, page_height - y - height, width, height,
+            ).bytes()),
+            Shape::Line { x1, y1, x2, y2, width, stroke } => content.extend(format!(
+                "{} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S\n",
+                pdf_color(stroke), width, x1, page_height - y1, x2, page_height - y2,
+            ).bytes()),
+            Shape::Text { x, y, size, text, color, anchor } => {
+                // Helvetica has no metrics available here, so approximate the advance
+                let text_width = text.chars().count() as f32 * size * GLYPH_WIDTH;
+                let x = match anchor {
+                    Anchor::Start => x,
+                    Anchor::Middle => x - text_width / 2.0,
+                    Anchor::End => x - text_width,
+                };
+                content.extend(format!(
+                    "BT {} rg /F1 {:.2} Tf {:.2} {:.2} Td ",
+                    pdf_color(color), size, x, page_height - y,
+                ).bytes());
+                content.extend(pdf_string(&text));
+                content.extend(b" Tj ET\n");
+            }
+        }
+    }
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>",
+            page_width, page_height,
+        ).into_bytes(),
+    ];
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend(content);
+    stream.extend(b"\nendstream");
+    objects.push(stream);
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", index + 1).bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref_offset = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset,
+    ).bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::time_format::HourCycle;
+
+    #[test]
+    fn activities_after_midnight_follow_the_evening() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let night_shift = Activity::new(0, "NIGHT SHIFT".to_string(), SimpleTime::new(23, 0, 0), SimpleTime::new(2, 0, 0));
+        let options = PrintOptions { range: PrintRange::Day, ..Default::default() };
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        let (_, shapes) = layout(&[night_shift], &options, &format, date, &SimpleTime::new(5, 0, 0), &Holidays::default(), true);
+
+        let blocks: Vec<(f32, f32)> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Rect { y, height, .. } => Some((*y, *height)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(blocks.len(), 1);
+        // 18 hours after the day start, lasting 3
+        let grid_y = MARGIN + TITLE_HEIGHT + HEADER_HEIGHT;
+        let (y, height) = blocks[0];
+        assert!((height / (y - grid_y) - 3.0 / 18.0).abs() < 0.001);
+
+        let hours: Vec<&str> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Text { text, anchor: Anchor::End, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hours.first(), Some(&"05:00"));
+        assert_eq!(hours.last(), Some(&"05:00"));
+        assert_eq!(hours.len(), 25);
+    }
+
+    #[test]
+    fn pdf_weekdays_fall_back_to_english() {
+        assert_eq!(weekday_name("понедельник".to_string(), Weekday::Mon, true), "Monday");
+        assert_eq!(weekday_name("日曜日".to_string(), Weekday::Sun, true), "Sunday");
+        assert_eq!(weekday_name("日曜日".to_string(), Weekday::Sun, false), "日曜日");
+        assert_eq!(weekday_name("Miércoles".to_string(), Weekday::Wed, true), "Miércoles");
+        assert_eq!(pdf_string("Miércoles (1…)"), b"(Mi\xe9rcoles \\(1\x85\\))".to_vec());
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{NaiveDate, Weekday};
+use super::structs::*;
+use super::time_format::TimeFormat;
+use super::time_input::parse_time;
+
+/// What a quick-add phrase was understood as
+pub struct QuickAdd {
+    pub name: String,
+    pub start_time: SimpleTime,
+    pub end_time: SimpleTime,
+    pub days: Days,
+    /// Whether the end was made up, as the phrase had no end or duration
+    pub assumed_end: bool,
+    /// Words after the name that weren't understood, and were left out
+    pub ignored: Vec<String>,
+}
+
+impl QuickAdd {
+    pub fn to_activity(&self, id: u32) -> Activity {
+        let mut activity = Activity::new(id, self.name.to_ascii_uppercase(), self.start_time, self.end_time);
+        activity.days = self.days.clone();
+        activity
+    }
+}
+
+/// A piece of the phrase that means something
+enum Part {
+    Start(SimpleTime),
+    End(SimpleTime),
+    Range(SimpleTime, SimpleTime),
+    Duration(u32),
+    Days(Days),
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+/// A weekday, and whether it was plural like "mondays"
+fn weekday_or_plural(word: &str) -> Option<(Weekday, bool)> {
+    weekday(word)
+        .map(|weekday| (weekday, false))
+        .or_else(|| weekday(word.strip_suffix('s')?).map(|weekday| (weekday, true)))
+}
+
+/// Reads a time at `words[0]`, taking a following "pm" along.
+/// Plain numbers like "9" only count as times when `bare` allows them, after words like "at".
+fn time(words: &[String], bare: bool) -> Option<(SimpleTime, usize)> {
+    let word = words.first()?;
+    if let Some(suffix) = words.get(1).filter(|suffix| ["am", "pm", "a.m.", "p.m."].contains(&suffix.as_str())) {
+        if let Some(time) = parse_time(&format!("{}{}", word, suffix)) {
+            return Some((time, 2));
+        }
+    }
+    let is_clock_time = word.contains([':', '.']) || word.ends_with(['a', 'p', 'm']);
+    if !bare && !is_clock_time {
+        return None;
+    }
+    parse_time(word).map(|time| (time, 1))
+}
+
+/// Reads a duration at `words[0]`: "1h", "45m", "1h30", "1.5h", "90 min", "2 hours" or "an hour"
+fn duration(words: &[String]) -> Option<(u32, usize)> {
+    let word = words.first()?;
+    let unit_seconds = |unit: &str| match unit {
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(3600.0),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60.0),
+        _ => None,
+    };
+
+    if let Some(unit) = words.get(1).and_then(|unit| unit_seconds(unit)) {
+        let amount = match word.as_str() {
+            "a" | "an" => Some(1.0),
+            amount => amount.parse::<f64>().ok(),
+        };
+        if let Some(amount) = amount {
+            return Some(((amount * unit).round() as u32, 2));
+        }
+    }
+
+    // "1h30" and "1h30m"
+    if let Some((hours, minutes)) = word.split_once('h').filter(|(_, minutes)| !minutes.is_empty()) {
+        let hours: u32 = hours.parse().ok()?;
+        let minutes: u32 = minutes.strip_suffix('m').unwrap_or(minutes).parse().ok()?;
+        // Too long to count saturates, to be turned down as ending after midnight
+        return Some((hours.saturating_mul(3600).saturating_add(minutes.saturating_mul(60)), 1));
+    }
+    let number_length = word.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(word.len());
+    let amount: f64 = word[..number_length].parse().ok()?;
+    let unit = unit_seconds(&word[number_length..])?;
+    Some(((amount * unit).round() as u32, 1))
+}
+
+/// Reads the days after "every": "day", "weekday", "weekend" or a list like "mon and wed"
+fn every(words: &[String]) -> Option<(Days, usize)> {
+    match words.first()?.as_str() {
+        "day" => return Some((Days::Every, 1)),
+        "weekday" | "weekdays" => return Some((Days::Weekdays(WORKDAYS.to_vec()), 1)),
+        "weekend" | "weekends" => return Some((Days::Weekdays(WEEKEND.to_vec()), 1)),
+        _ => {}
+    }
+    let mut weekdays = Vec::new();
+    let mut used = 0;
+    for (index, word) in words.iter().enumerate() {
+        if let Some((weekday, _)) = weekday_or_plural(word) {
+            weekdays.push(weekday);
+            used = index + 1;
+        } else if word != "and" {
+            break;
+        }
+    }
+    if weekdays.is_empty() {
+        return None;
+    }
+    weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+    weekdays.dedup();
+    Some((Days::Weekdays(weekdays), used))
+}
+
+/// Reads the meaningful part starting at `words[0]`, with how many words it takes
+fn part(words: &[String], today: NaiveDate) -> Option<(Part, usize)> {
+    let word = words.first()?.as_str();
+    let rest = &words[1..];
+    let plus_one = |(part, used): (Part, usize)| (part, used + 1);
+    match word {
+        "at" | "from" => time(rest, true).map(|(time, used)| (Part::Start(time), used + 1)),
+        "to" | "until" | "till" | "-" | "–" => time(rest, true).map(|(time, used)| (Part::End(time), used + 1)),
+        "for" => duration(rest).map(|(seconds, used)| (Part::Duration(seconds), used + 1)),
+        "every" | "each" => every(rest).map(|(days, used)| (Part::Days(days), used + 1)),
+        "on" => part(rest, today)
+            .filter(|(part, _)| matches!(part, Part::Days(_)))
+            .map(plus_one)
+            .or_else(|| {
+                // Short names like "on wed" only count after "on"
+                let weekday = weekday(rest.first()?)?;
+                Some((Part::Days(Days::Date(next_date(today, weekday))), 2))
+            }),
+        "daily" => Some((Part::Days(Days::Every), 1)),
+        "weekdays" => Some((Part::Days(Days::Weekdays(WORKDAYS.to_vec())), 1)),
+        "weekends" => Some((Part::Days(Days::Weekdays(WEEKEND.to_vec())), 1)),
+        "today" | "tonight" => Some((Part::Days(Days::Date(today)), 1)),
+        "tomorrow" => Some((Part::Days(Days::Date(today.succ_opt()?)), 1)),
+        _ => {
+            if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                return Some((Part::Days(Days::Date(date)), 1));
+            }
+            if word.len() > 3 {
+                if let Some((weekday, plural)) = weekday_or_plural(word) {
+                    let days = if plural {
+                        Days::Weekdays(vec![weekday])
+                    } else {
+                        Days::Date(next_date(today, weekday))
+                    };
+                    return Some((Part::Days(days), 1));
+                }
+            }
+            if let Some((start, end)) = word.split_once(['-', '–']) {
+                let (start, _) = time(&[start.to_string()], true)?;
+                let (end, _) = time(&[end.to_string()], true)?;
+                return Some((Part::Range(start, end), 1));
+            }
+            time(words, false).map(|(time, used)| (Part::Start(time), used))
+        }
+    }
+}
+
+/// Understands phrases like "gym 18:00 for 1h", "lunch 12:30-13:15 every weekday"
+/// or "call mom tomorrow at 8pm". The name is everything before the first time or day.
+/// Activities can run past midnight when the plan day starting at `day_start` does.
+pub fn parse(text: &str, today: NaiveDate, day_start: &SimpleTime, format: &TimeFormat) -> Result<QuickAdd, String> {
+    let original: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = original
+        .iter()
+        .map(|word| word.trim_end_matches(',').to_lowercase())
+        .collect();
+
+    let mut name = Vec::new();
+    let mut ignored = Vec::new();
+    let (mut start_time, mut end_time, mut duration, mut days) = (None, None, None, None);
+    let mut index = 0;
+    while index < words.len() {
+        let Some((part, used)) = part(&words[index..], today) else {
+            if start_time.is_none() && end_time.is_none() && duration.is_none() && days.is_none() {
+                name.push(original[index]);
+            } else {
+                ignored.push(original[index].to_string());
+            }
+            index += 1;
+            continue;
+        };
+        match part {
+            // A second time is taken as the end
+            Part::Start(time) if start_time.is_some() && end_time.is_none() => end_time = Some(time),
+            Part::Start(time) => start_time = Some(time),
+            Part::End(time) => end_time = Some(time),
+            Part::Range(start, end) => {
+                start_time = Some(start);
+                end_time = Some(end);
+            }
+            Part::Duration(seconds) => duration = Some(seconds),
+            Part::Days(new_days) => days = Some(new_days),
+        }
+        index += used;
+    }
+
+    let name = name.join(" ");
+    if name.is_empty() {
+        return Err("Missing activity name".to_string());
+    }
+    let start_time = start_time.ok_or("Missing start time, like \"at 9:30\"")?;
+    // Seconds into the plan day. Huge durations saturate when read, so they're added with a check.
+    let start_seconds = start_time.seconds_into_day(day_start);
+    let end_seconds = match (end_time, duration) {
+        (Some(end_time), _) => Some(end_time.seconds_into_day(day_start)),
+        (None, Some(duration)) => start_seconds.checked_add(duration),
+        (None, None) => Some(start_seconds + DEFAULT_DURATION_SECONDS),
+    }
+    .ok_or_else(|| ends_after_day_end(day_start, format))?;
+    if end_seconds <= start_seconds {
+        return Err("Ends before it starts".to_string());
+    }
+    if end_seconds >= SECONDS_PER_DAY {
+        return Err(ends_after_day_end(day_start, format));
+    }
+    let days = days.unwrap_or_default();
+    if matches!(days, Days::Date(date) if date < today) {
+        return Err("That day has passed".to_string());
+    }
+    Ok(QuickAdd {
+        name,
+        start_time,
+        end_time: SimpleTime::from_seconds((end_seconds + day_start.as_seconds()) % SECONDS_PER_DAY),
+        days,
+        assumed_end: end_time.is_none() && duration.is_none(),
+        ignored,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::time_format::HourCycle;
+
+    fn parse(text: &str, today: NaiveDate, day_start: &SimpleTime) -> Result<QuickAdd, String> {
+        super::parse(text, today, day_start, &TimeFormat::new(HourCycle::TwentyFourHour))
+    }
+
+    fn midnight() -> SimpleTime {
+        SimpleTime::new(0, 0, 0)
+    }
+
+    fn today() -> NaiveDate {
+        // A Monday
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn parses_times_durations_and_days() {
+        let quick_add = parse("gym 18:00 for 1h30", today(), &midnight()).unwrap();
+        assert_eq!(quick_add.name, "gym");
+        assert_eq!(quick_add.start_time, SimpleTime::new(18, 0, 0));
+        assert_eq!(quick_add.end_time, SimpleTime::new(19, 30, 0));
+        assert_eq!(quick_add.days, Days::Every);
+
+        let quick_add = parse("lunch 12:30-13:15 every weekday", today(), &midnight()).unwrap();
+        assert_eq!(quick_add.end_time, SimpleTime::new(13, 15, 0));
+        assert_eq!(quick_add.days, Days::Weekdays(WORKDAYS.to_vec()));
+
+        let quick_add = parse("call mom tomorrow at 8pm", today(), &midnight()).unwrap();
+        assert_eq!(quick_add.name, "call mom");
+        assert_eq!(quick_add.start_time, SimpleTime::new(20, 0, 0));
+        assert!(quick_add.assumed_end);
+        assert_eq!(quick_add.days, Days::Date(today().succ_opt().unwrap()));
+    }
+
+    #[test]
+    fn huge_durations_end_after_midnight() {
+        for text in ["gym at 9 for 2000000 hours", "gym at 9 for 99999999h30", "gym at 9 for 4294967295 min"] {
+            assert_eq!(parse(text, today(), &midnight()).err().as_deref(), Some("Ends after midnight"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn non_ascii_words_are_names() {
+        let quick_add = parse("élan-x at 9", today(), &midnight()).unwrap();
+        assert_eq!(quick_add.name, "élan-x");
+        assert!(parse("１２", today(), &midnight()).is_err());
+    }
+
+    #[test]
+    fn overnight_ranges_follow_the_day_start() {
+        let day_start = SimpleTime::new(5, 0, 0);
+        let quick_add = parse("night shift 23:00-01:00", today(), &day_start).unwrap();
+        assert_eq!((quick_add.start_time, quick_add.end_time), (SimpleTime::new(23, 0, 0), SimpleTime::new(1, 0, 0)));
+        let quick_add = parse("party at 11pm for 3h", today(), &day_start).unwrap();
+        assert_eq!(quick_add.end_time, SimpleTime::new(2, 0, 0));
+
+        assert_eq!(parse("night shift 23:00-01:00", today(), &midnight()).err().as_deref(), Some("Ends before it starts"));
+        assert_eq!(
+            parse("party at 11pm for 7h", today(), &day_start).err().as_deref(),
+            Some("Ends after the day ends at 05:00"),
+        );
+        // Worded as in the activity form, in the time format of the settings
+        let format = TimeFormat::new(HourCycle::TwelveHour);
+        assert_eq!(
+            super::parse("party at 11pm for 7h", today(), &day_start, &format).err(),
+            Some(ends_after_day_end(&day_start, &format)),
+        );
+        assert_eq!(ends_after_day_end(&day_start, &format), format!("Ends after the day ends at {}", format.time(&day_start)));
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Serialize, Deserialize};
+use super::notifier::Notification;
+use super::structs::*;
+
+/// A daily window where notifications are held back, which may cross midnight
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start_time: SimpleTime,
+    pub end_time: SimpleTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: &SimpleTime) -> bool {
+        let start = self.start_time.as_seconds();
+        let end = self.end_time.as_seconds();
+        let time = time.as_seconds();
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        QuietHours {
+            start_time: SimpleTime::new(22, 0, 0),
+            end_time: SimpleTime::new(7, 0, 0),
+        }
+    }
+}
+
+/// What happens to notifications during quiet hours or from silent activities
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum QuietMode {
+    /// Don't show them at all
+    Suppress,
+    /// Show them as in-app toasts only, without sound
+    Downgrade,
+}
+
+impl QuietMode {
+    pub const ALL: [QuietMode; 2] = [QuietMode::Suppress, QuietMode::Downgrade];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QuietMode::Suppress => "Suppress",
+            QuietMode::Downgrade => "In-app only",
+        }
+    }
+}
+
+pub fn is_quiet(quiet_hours: &[QuietHours], time: &SimpleTime) -> bool {
+    quiet_hours.iter().any(|window| window.contains(time))
+}
+
+/// Where a notification goes, given quiet hours and whether its activity is silent
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Delivery {
+    /// Through the chosen backend, with sound
+    Show,
+    /// As an in-app toast, without sound
+    InApp,
+    /// Kept for the summary once quiet hours end
+    Hold,
+    Drop,
+}
+
+pub fn delivery(is_silent: bool, is_quiet: bool, mode: QuietMode, summarize: bool) -> Delivery {
+    if !is_silent && !is_quiet {
+        return Delivery::Show;
+    }
+    match mode {
+        QuietMode::Downgrade => Delivery::InApp,
+        // Silent activities stay silent after quiet hours too
+        QuietMode::Suppress if is_quiet && summarize => Delivery::Hold,
+        QuietMode::Suppress => Delivery::Drop,
+    }
+}
+
+/// Sums up the notifications held back, once quiet hours end
+pub fn summary(held: &mut Vec<Notification>, was_quiet: bool, is_quiet: bool) -> Option<Notification> {
+    if !was_quiet || is_quiet || held.is_empty() {
+        return None;
+    }
+    let body = held
+        .drain(..)
+        .map(|notification| notification.body)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Notification::new("Quiet Hours Ended", &body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u8, minute: u8, second: u8) -> SimpleTime {
+        SimpleTime::new(hour, minute, second)
+    }
+
+    #[test]
+    fn night_window_wraps_midnight() {
+        let night = QuietHours::default();
+        for time in [at(22, 0, 0), at(23, 59, 59), at(0, 0, 0), at(3, 0, 0), at(6, 59, 59)] {
+            assert!(night.contains(&time), "{}", time);
+        }
+        for time in [at(21, 59, 59), at(7, 0, 0), at(12, 0, 0)] {
+            assert!(!night.contains(&time), "{}", time);
+        }
+    }
+
+    #[test]
+    fn day_window_has_the_same_edges() {
+        let lunch = QuietHours {
+            start_time: at(12, 0, 0),
+            end_time: at(13, 0, 0),
+        };
+        assert!(!lunch.contains(&at(11, 59, 59)));
+        assert!(lunch.contains(&at(12, 0, 0)));
+        assert!(lunch.contains(&at(12, 59, 59)));
+        assert!(!lunch.contains(&at(13, 0, 0)));
+        assert!(is_quiet(&[QuietHours::default(), lunch], &at(12, 30, 0)));
+        assert!(!is_quiet(&[], &at(23, 0, 0)));
+    }
+
+    #[test]
+    fn delivery_follows_the_mode() {
+        assert_eq!(delivery(false, false, QuietMode::Suppress, true), Delivery::Show);
+        assert_eq!(delivery(false, true, QuietMode::Suppress, true), Delivery::Hold);
+        assert_eq!(delivery(false, true, QuietMode::Suppress, false), Delivery::Drop);
+        assert_eq!(delivery(true, false, QuietMode::Suppress, true), Delivery::Drop);
+        assert_eq!(delivery(true, false, QuietMode::Downgrade, true), Delivery::InApp);
+        assert_eq!(delivery(false, true, QuietMode::Downgrade, true), Delivery::InApp);
+    }
+
+    #[test]
+    fn held_notifications_are_summed_up_when_quiet_hours_end() {
+        let mut held = vec![Notification::new("Start", "23:00 READING started"), Notification::new("End", "23:30 READING ended")];
+        // Still quiet, or wasn't quiet before
+        assert_eq!(summary(&mut held, true, true), None);
+        assert_eq!(summary(&mut held, false, false), None);
+        assert_eq!(held.len(), 2);
+
+        let summary_notification = summary(&mut held, true, false).unwrap();
+        assert_eq!(summary_notification.summary, "Quiet Hours Ended");
+        assert_eq!(summary_notification.body, "23:00 READING started\n23:30 READING ended");
+        assert!(held.is_empty());
+        assert_eq!(summary(&mut held, true, false), None);
+    }
+}
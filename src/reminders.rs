@@ -0,0 +1,201 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use super::clock::Clock;
+use super::history::History;
+use super::holidays::Holidays;
+use super::settings::Settings;
+use super::structs::*;
+
+/// Lead times offered by the reminder pickers, in minutes
+pub const REMINDER_CHOICES: [u32; 6] = [5, 10, 15, 20, 30, 60];
+
+/// Intervals offered for repeating the start of insistent activities, in minutes
+pub const REPEAT_CHOICES: [u32; 6] = [1, 2, 5, 10, 15, 30];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ReminderKind {
+    BeforeStart,
+    BeforeEnd,
+}
+
+pub struct DueReminder {
+    pub activity_id: u32,
+    /// Plan day of the activity, which is the next one for a reminder due before it starts
+    pub date: NaiveDate,
+    pub kind: ReminderKind,
+    /// When the start or end warned of happens, which moves when the activity is extended
+    pub event: DateTime<Utc>,
+    pub minutes_left: u32,
+}
+
+/// Minutes of warning for the activity, falling back to the global default. 0 means off.
+pub fn lead_minutes(activity: &Activity, kind: ReminderKind, settings: &Settings) -> u32 {
+    match kind {
+        ReminderKind::BeforeStart => activity.remind_before_start.unwrap_or(settings.remind_before_start),
+        ReminderKind::BeforeEnd => activity.remind_before_end.unwrap_or(settings.remind_before_end),
+    }
+}
+
+/// When an insistent activity's start notification is repeated on the plan day `date`,
+/// counting from when the activity really starts
+pub fn repeat_times(activity: &Activity, clock: &dyn Clock, date: NaiveDate, history: &History) -> Vec<DateTime<Utc>> {
+    let Some(insistence) = activity.insistent.filter(|insistence| insistence.every_minutes > 0) else {
+        return Vec::new();
+    };
+    let Some((start, _)) = activity.span(clock, date, history) else {
+        return Vec::new();
+    };
+    (1..=insistence.max_repeats)
+        .map(|repeat| start + Duration::minutes((repeat * insistence.every_minutes) as i64))
+        .collect()
+}
+
+/// How many repeats of an insistent activity's start notification are due by now, on the current plan day
+pub fn due_repeats(activity: &Activity, clock: &dyn Clock, history: &History) -> u32 {
+    let now = clock.now_utc();
+    repeat_times(activity, clock, clock.today(), history)
+        .into_iter()
+        .filter(|time| *time <= now)
+        .count() as u32
+}
+
+/// Whole minutes since an activity started on the current plan day, for the repeat notification
+pub fn minutes_since_start(activity: &Activity, clock: &dyn Clock, history: &History) -> u32 {
+    activity
+        .span(clock, clock.today(), history)
+        .map_or(0, |(start, _)| (clock.now_utc() - start).num_minutes().max(0) as u32)
+}
+
+/// When the reminder of `kind` is due on the plan day `date`, from the lead time until the event.
+/// The lead time counts back from when the event really happens: around a daylight saving change,
+/// an activity starting in the skipped hour is reminded of that long before the end of the gap,
+/// and one in the repeated hour that long before it first comes round.
+pub fn window(
+    activity: &Activity,
+    kind: ReminderKind,
+    settings: &Settings,
+    clock: &dyn Clock,
+    date: NaiveDate,
+    history: &History,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let lead = lead_minutes(activity, kind, settings) as i64 * 60;
+    if lead == 0 {
+        return None;
+    }
+    let (start, end) = activity.span(clock, date, history)?;
+    let event = match kind {
+        ReminderKind::BeforeStart => start,
+        ReminderKind::BeforeEnd => end,
+    };
+    Some((event - Duration::seconds(lead), event))
+}
+
+/// Reminders whose window contains the current time, for activities on the current plan day
+/// or early on the next one, whose warning comes before it starts
+pub fn due_reminders(
+    activities: &[Activity],
+    settings: &Settings,
+    clock: &dyn Clock,
+    holidays: &Holidays,
+    history: &History,
+) -> Vec<DueReminder> {
+    let now = clock.now_utc();
+    let today = clock.today();
+    let mut due = Vec::new();
+    for date in [today, today.succ_opt().unwrap_or(today)] {
+        for activity in activities.iter().filter(|activity| activity.occurs_on(date, holidays)) {
+            for kind in [ReminderKind::BeforeStart, ReminderKind::BeforeEnd] {
+                let Some((from, event)) = window(activity, kind, settings, clock, date, history) else {
+                    continue;
+                };
+                if from <= now && now < event {
+                    due.push(DueReminder {
+                        activity_id: activity.id,
+                        date,
+                        kind,
+                        event,
+                        minutes_left: ((event - now).num_seconds() as u32).div_ceil(60),
+                    });
+                }
+            }
+        }
+    }
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+    use super::super::time_zone::HomeClock;
+
+    fn berlin_at(hour: u32, minute: u32) -> HomeClock {
+        HomeClock::fixed(Utc.with_ymd_and_hms(2026, 3, 29, hour, minute, 0).unwrap(), chrono_tz::Europe::Berlin, SimpleTime::new(0, 0, 0))
+    }
+
+    #[test]
+    fn reminder_in_the_skipped_hour_comes_before_the_end_of_the_gap() {
+        // 02:30 doesn't happen on the spring-forward day, so the activity starts at 03:00 CEST
+        let mut activity = Activity::new(0, "EARLY CALL".to_string(), SimpleTime::new(2, 30, 0), SimpleTime::new(3, 30, 0));
+        activity.remind_before_start = Some(10);
+        let activities = [activity];
+        let due = |clock: &HomeClock| due_reminders(&activities, &Settings::default(), clock, &Holidays::default(), &History::default());
+
+        assert!(due(&berlin_at(0, 49)).is_empty());
+        // 01:50 CET
+        let reminders = due(&berlin_at(0, 50));
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].kind, ReminderKind::BeforeStart);
+        assert_eq!(reminders[0].minutes_left, 10);
+        assert!(due(&berlin_at(1, 0)).is_empty());
+    }
+
+    #[test]
+    fn extending_moves_the_end_reminder() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let mut activity = Activity::new(0, "DEEP WORK".to_string(), SimpleTime::new(9, 0, 0), SimpleTime::new(10, 0, 0));
+        activity.remind_before_end = Some(10);
+        let activities = [activity];
+        let mut history = History::default();
+        history.extend(date, 0, 15 * 60);
+        let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, 19, 10, 5, 0).unwrap(), chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+
+        let due = due_reminders(&activities, &Settings::default(), &clock, &Holidays::default(), &history);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].event, Utc.with_ymd_and_hms(2026, 10, 19, 10, 15, 0).unwrap());
+        assert_eq!(due[0].minutes_left, 10);
+    }
+
+    #[test]
+    fn repeats_follow_the_day_start() {
+        // The plan day of the 19th runs until 05:00 on the 20th, so 02:00 is after midnight
+        let mut activity = Activity::new(0, "NIGHT FEED".to_string(), SimpleTime::new(2, 0, 0), SimpleTime::new(2, 15, 0));
+        activity.insistent = Some(Insistence { every_minutes: 5, max_repeats: 3 });
+        let history = History::default();
+        let at = |day: u32, hour: u32, minute: u32| {
+            HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, day, hour, minute, 0).unwrap(), chrono_tz::UTC, SimpleTime::new(5, 0, 0))
+        };
+
+        assert_eq!(due_repeats(&activity, &at(19, 23, 0), &history), 0);
+        assert_eq!(due_repeats(&activity, &at(20, 2, 4), &history), 0);
+        assert_eq!(due_repeats(&activity, &at(20, 2, 10), &history), 2);
+        assert_eq!(due_repeats(&activity, &at(20, 4, 0), &history), 3);
+        assert_eq!(minutes_since_start(&activity, &at(20, 2, 10), &history), 10);
+        assert_eq!(minutes_since_start(&activity, &at(19, 23, 0), &history), 0);
+    }
+
+    #[test]
+    fn reminders_before_the_day_start_come_the_evening_before() {
+        let mut activity = Activity::new(0, "EARLY CALL".to_string(), SimpleTime::new(0, 5, 0), SimpleTime::new(0, 30, 0));
+        activity.remind_before_start = Some(10);
+        let activities = [activity];
+        let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, 19, 23, 56, 0).unwrap(), chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+
+        let due = due_reminders(&activities, &Settings::default(), &clock, &Holidays::default(), &History::default());
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].date, NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        assert_eq!(due[0].kind, ReminderKind::BeforeStart);
+        assert_eq!(due[0].event, Utc.with_ymd_and_hms(2026, 10, 20, 0, 5, 0).unwrap());
+        assert_eq!(due[0].minutes_left, 9);
+    }
+}
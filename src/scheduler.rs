@@ -0,0 +1,197 @@
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use super::clock::Clock;
+use super::digest::DigestKind;
+use super::history::History;
+use super::reminders::{self, ReminderKind};
+use super::settings::Settings;
+use super::structs::*;
+
+/// Instants on the plan day `date` when something in the plan may change:
+/// activities starting or ending, and reminders and repeats coming due
+fn plan_instants(
+    activities: &[Activity],
+    settings: &Settings,
+    clock: &dyn Clock,
+    date: NaiveDate,
+    history: &History,
+) -> Vec<DateTime<Utc>> {
+    let mut instants = Vec::new();
+    for activity in activities {
+        let Some((start, end)) = activity.span(clock, date, history) else {
+            continue;
+        };
+        instants.push(start);
+        // Activities are still running during their last second
+        instants.push(end + Duration::seconds(1));
+        for kind in [ReminderKind::BeforeStart, ReminderKind::BeforeEnd] {
+            instants.extend(reminders::window(activity, kind, settings, clock, date, history).map(|(from, _)| from));
+        }
+        instants.extend(reminders::repeat_times(activity, clock, date, history));
+    }
+    instants
+}
+
+/// Local times of day, in seconds since midnight, when digests come due and quiet hours start or end
+fn local_seconds(settings: &Settings) -> Vec<u32> {
+    let mut seconds = Vec::new();
+    for kind in DigestKind::ALL {
+        if let Some(time) = settings.digest_time(kind) {
+            seconds.push(time.as_seconds());
+        }
+    }
+    for window in &settings.quiet_hours {
+        seconds.push(window.start_time.as_seconds());
+        seconds.push(window.end_time.as_seconds());
+    }
+    seconds
+}
+
+/// When the app should check the plan next: the next time something in the plan comes due,
+/// or at the latest when the next plan day starts. Plan times are taken from the activities' spans,
+/// which follow daylight saving changes and extensions, so wakeups stay on time across them.
+/// Digests and quiet hours follow `local_clock`, as they stay in the local time while travelling.
+pub fn next_wakeup(
+    activities: &[Activity],
+    settings: &Settings,
+    clock: &dyn Clock,
+    local_clock: &dyn Clock,
+    history: &History,
+) -> DateTime<Utc> {
+    let now = clock.now_utc();
+    let plan_today = clock.today();
+    let plan_tomorrow = plan_today.succ_opt().unwrap_or(plan_today);
+    let next_day_start = clock.plan_instant(plan_tomorrow, &clock.day_start());
+    let local_today = local_clock.now().date();
+    let local_tomorrow = local_today.succ_opt().unwrap_or(local_today);
+    let local_instants = local_seconds(settings)
+        .into_iter()
+        .filter(|seconds| *seconds < SECONDS_PER_DAY)
+        .flat_map(|seconds| {
+            let time = SimpleTime::from_seconds(seconds);
+            [local_clock.instant_of(local_today, &time), local_clock.instant_of(local_tomorrow, &time)]
+        });
+    [plan_today, plan_tomorrow]
+        .into_iter()
+        .flat_map(|date| plan_instants(activities, settings, clock, date, history))
+        .chain(local_instants)
+        .filter(|instant| *instant > now)
+        .fold(next_day_start, |earliest, instant| earliest.min(instant))
+}
+
+/// When the next minute starts, for the status panel and the current-time line to move on
+pub fn next_minute(now: DateTime<Utc>) -> DateTime<Utc> {
+    let this_minute = now.with_second(0).and_then(|now| now.with_nanosecond(0)).unwrap_or(now);
+    this_minute + Duration::minutes(1)
+}
+
+/// Real time to wait until `next_wakeup` on a clock running at `speed`, or until the first of
+/// `other_delays`, like toasts to hide, if that's sooner
+pub fn real_delay(
+    next_wakeup: DateTime<Utc>,
+    now: DateTime<Utc>,
+    speed: f64,
+    other_delays: impl IntoIterator<Item = std::time::Duration>,
+) -> std::time::Duration {
+    let until_wakeup = (next_wakeup - now).to_std().unwrap_or_default();
+    let delay = std::time::Duration::from_secs_f64(until_wakeup.as_secs_f64() / speed);
+    other_delays.into_iter().fold(delay, |delay, other_delay| delay.min(other_delay))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+    use super::super::quiet_hours::QuietHours;
+    use super::super::time_zone::HomeClock;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, hour, minute, second).unwrap()
+    }
+
+    fn wakeup(activities: &[Activity], settings: &Settings, now: DateTime<Utc>) -> DateTime<Utc> {
+        let clock = HomeClock::fixed(now, chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+        next_wakeup(activities, settings, &clock, &clock, &History::default())
+    }
+
+    #[test]
+    fn without_activities_wakes_up_when_the_next_day_starts() {
+        assert_eq!(wakeup(&[], &Settings::default(), at(10, 0, 20)), Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap());
+        let clock = HomeClock::fixed(at(10, 0, 20), chrono_tz::UTC, SimpleTime::new(5, 0, 0));
+        assert_eq!(
+            next_wakeup(&[], &Settings::default(), &clock, &clock, &History::default()),
+            Utc.with_ymd_and_hms(2026, 10, 20, 5, 0, 0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn the_status_moves_on_every_minute() {
+        assert_eq!(next_minute(at(10, 0, 20)), at(10, 1, 0));
+        assert_eq!(next_minute(at(23, 59, 0)), Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn wakes_up_for_the_next_start_and_end() {
+        let activities = [
+            Activity::new(0, "STANDUP".to_string(), SimpleTime::new(10, 0, 45), SimpleTime::new(10, 15, 0)),
+            Activity::new(1, "LUNCH".to_string(), SimpleTime::new(12, 0, 0), SimpleTime::new(13, 0, 0)),
+        ];
+        assert_eq!(wakeup(&activities, &Settings::default(), at(10, 0, 20)), at(10, 0, 45));
+        // Ends are checked once the last second has passed
+        assert_eq!(wakeup(&activities, &Settings::default(), at(10, 15, 0)), at(10, 15, 1));
+        // Nothing happens in between, however far away the next activity is
+        assert_eq!(wakeup(&activities, &Settings::default(), at(11, 0, 20)), at(12, 0, 0));
+    }
+
+    #[test]
+    fn quiet_hours_and_reminders_wake_up_earlier() {
+        let settings = Settings {
+            quiet_hours: vec![QuietHours {
+                start_time: SimpleTime::new(22, 0, 40),
+                end_time: SimpleTime::new(7, 0, 10),
+            }],
+            remind_before_start: 5,
+            ..Default::default()
+        };
+        assert_eq!(wakeup(&[], &settings, at(22, 0, 0)), at(22, 0, 40));
+        assert_eq!(wakeup(&[], &settings, at(7, 0, 0)), at(7, 0, 10));
+
+        let gym = [Activity::new(0, "GYM".to_string(), SimpleTime::new(18, 5, 30), SimpleTime::new(19, 0, 0))];
+        assert_eq!(wakeup(&gym, &settings, at(18, 0, 0)), at(18, 0, 30));
+    }
+
+    #[test]
+    fn reminders_before_the_day_start_wake_up_the_evening_before() {
+        let mut early_call = Activity::new(0, "EARLY CALL".to_string(), SimpleTime::new(0, 5, 30), SimpleTime::new(0, 30, 0));
+        early_call.remind_before_start = Some(10);
+        assert_eq!(wakeup(&[early_call], &Settings::default(), at(23, 55, 0)), at(23, 55, 30));
+    }
+
+    #[test]
+    fn digests_follow_the_local_clock() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 19, 8, 0, 0).unwrap();
+        let settings = Settings {
+            start_of_day_digest: Some(SimpleTime::new(8, 0, 30)),
+            ..Default::default()
+        };
+        // The plan stays in Tokyo while the traveller is in UTC
+        let plan_clock = HomeClock::fixed(now, chrono_tz::Asia::Tokyo, SimpleTime::new(0, 0, 0));
+        let local_clock = HomeClock::fixed(now, chrono_tz::UTC, SimpleTime::new(0, 0, 0));
+        assert_eq!(
+            next_wakeup(&[], &settings, &plan_clock, &local_clock, &History::default()),
+            now + Duration::seconds(30),
+        );
+    }
+
+    #[test]
+    fn toasts_wake_up_earlier() {
+        let wakeup = at(10, 1, 0);
+        let now = at(10, 0, 20);
+        assert_eq!(real_delay(wakeup, now, 1.0, []), std::time::Duration::from_secs(40));
+        assert_eq!(real_delay(wakeup, now, 1.0, [std::time::Duration::from_secs(10)]), std::time::Duration::from_secs(10));
+        assert_eq!(real_delay(wakeup, now, 1.0, [std::time::Duration::from_secs(90)]), std::time::Duration::from_secs(40));
+        // A simulated clock running ten times faster gets there sooner
+        assert_eq!(real_delay(wakeup, now, 10.0, []), std::time::Duration::from_secs(4));
+        // A wakeup already past is due right away
+        assert_eq!(real_delay(now, wakeup, 1.0, []), std::time::Duration::ZERO);
+    }
+}
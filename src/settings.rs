@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use super::digest::DigestKind;
+use super::notifier::NotificationBackend;
+use super::plan_format::PlanFormat;
+use super::quiet_hours::{QuietHours, QuietMode};
+use super::sound::SoundSettings;
+use super::structs::SimpleTime;
+use super::templates::NotificationTemplates;
+use super::time_format::HourCycle;
+use super::time_input::TimeEntry;
+use super::time_zone::TravelMode;
+
+const SETTINGS_PATH: &str = "settings.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Format of the plan file written by "Save Plan"
+    pub plan_format: PlanFormat,
+    pub pretty_json: bool,
+    pub notification_backend: NotificationBackend,
+    /// Default minutes of warning before an activity starts, 0 for none
+    pub remind_before_start: u32,
+    /// Default minutes of warning before an activity ends, 0 for none
+    pub remind_before_end: u32,
+    pub sounds: SoundSettings,
+    pub quiet_hours: Vec<QuietHours>,
+    pub quiet_mode: QuietMode,
+    /// Show what was suppressed in a single notification once quiet hours end
+    pub summarize_quiet_hours: bool,
+    /// When to send the daily digests, `None` for never
+    pub start_of_day_digest: Option<SimpleTime>,
+    pub end_of_day_digest: Option<SimpleTime>,
+    pub templates: NotificationTemplates,
+    /// Whether plans with a home time zone keep to it while travelling
+    pub travel_mode: TravelMode,
+    pub hour_cycle: HourCycle,
+    pub time_entry: TimeEntry,
+    /// Hour the plan's days start at, for days running past midnight
+    pub day_start_hour: u8,
+    /// First and last hour shown on the timeline, `None` for the whole day
+    pub visible_hours: Option<(u8, u8)>,
+    /// Holiday calendars, as .ics files or lists of dates
+    pub holiday_calendars: Vec<PathBuf>,
+    /// Days off marked by hand, counting as holidays
+    pub days_off: BTreeSet<NaiveDate>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            plan_format: PlanFormat::Json,
+            pretty_json: false,
+            notification_backend: NotificationBackend::Desktop,
+            remind_before_start: 0,
+            remind_before_end: 0,
+            sounds: SoundSettings::default(),
+            quiet_hours: Vec::new(),
+            quiet_mode: QuietMode::Suppress,
+            summarize_quiet_hours: true,
+            start_of_day_digest: None,
+            end_of_day_digest: None,
+            templates: NotificationTemplates::default(),
+            travel_mode: TravelMode::StayAnchored,
+            hour_cycle: HourCycle::System,
+            time_entry: TimeEntry::default(),
+            day_start_hour: 0,
+            visible_hours: None,
+            holiday_calendars: Vec::new(),
+            days_off: BTreeSet::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn digest_time(&self, kind: DigestKind) -> Option<SimpleTime> {
+        match kind {
+            DigestKind::StartOfDay => self.start_of_day_digest,
+            DigestKind::EndOfDay => self.end_of_day_digest,
+        }
+    }
+
+    pub fn digest_time_mut(&mut self, kind: DigestKind) -> &mut Option<SimpleTime> {
+        match kind {
+            DigestKind::StartOfDay => &mut self.start_of_day_digest,
+            DigestKind::EndOfDay => &mut self.end_of_day_digest,
+        }
+    }
+
+    /// Loads the saved settings, or the defaults when there are none yet
+    pub fn load() -> std::io::Result<Self> {
+        match std::fs::read_to_string(SETTINGS_PATH) {
+            Ok(settings_json) => Settings::from_json(&settings_json),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Reads settings, which may have been edited by hand, rejecting hours that aren't on the clock
+    fn from_json(settings_json: &str) -> std::io::Result<Self> {
+        let settings: Settings = serde_json::from_str(settings_json)?;
+        let (first, last) = settings.visible_hours.unzip();
+        for (name, hour) in [("day_start_hour", Some(settings.day_start_hour)), ("visible_hours", first), ("visible_hours", last)] {
+            if let Some(hour) = hour.filter(|hour| *hour > 23) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{} has the hour {}, expected 0 to 23", name, hour),
+                ));
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn path() -> &'static str {
+        SETTINGS_PATH
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let settings_json = serde_json::to_string_pretty(self)?;
+        std::fs::write(SETTINGS_PATH, settings_json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_off_the_clock_are_rejected() {
+        assert_eq!(Settings::from_json(r#"{"day_start_hour": 5, "visible_hours": [6, 23]}"#).unwrap().visible_hours, Some((6, 23)));
+        for settings_json in [r#"{"day_start_hour": 232}"#, r#"{"visible_hours": [250, 10]}"#, r#"{"visible_hours": [6, 24]}"#] {
+            let error = Settings::from_json(settings_json).err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", settings_json);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use serde::{Serialize, Deserialize};
+use super::notifier::NotificationEvent;
+
+const START_SOUND: &[u8] = include_bytes!("sounds/start.wav");
+const END_SOUND: &[u8] = include_bytes!("sounds/end.wav");
+const REMINDER_SOUND: &[u8] = include_bytes!("sounds/reminder.wav");
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoundSource {
+    Bundled(NotificationEvent),
+    File(PathBuf),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub enabled: bool,
+    /// From 0.0 to 1.0
+    pub volume: f32,
+    /// Files replacing the bundled sounds, `None` to keep the bundled one
+    pub start: Option<PathBuf>,
+    pub end: Option<PathBuf>,
+    pub reminder: Option<PathBuf>,
+    /// Files used for every event of activities in a category
+    pub categories: BTreeMap<String, PathBuf>,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings {
+            enabled: false,
+            volume: 0.8,
+            start: None,
+            end: None,
+            reminder: None,
+            categories: BTreeMap::new(),
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn event_file_mut(&mut self, event: NotificationEvent) -> &mut Option<PathBuf> {
+        match event {
+            NotificationEvent::Start => &mut self.start,
+            NotificationEvent::End => &mut self.end,
+            NotificationEvent::Reminder => &mut self.reminder,
+        }
+    }
+
+    /// Picks the category override first, then the event's file, then the bundled sound
+    pub fn source(&self, event: NotificationEvent, category: &str) -> SoundSource {
+        let event_file = match event {
+            NotificationEvent::Start => &self.start,
+            NotificationEvent::End => &self.end,
+            NotificationEvent::Reminder => &self.reminder,
+        };
+        match self.categories.get(category).or(event_file.as_ref()) {
+            Some(path) => SoundSource::File(path.clone()),
+            None => SoundSource::Bundled(event),
+        }
+    }
+
+    /// Builds the player for these settings
+    pub fn player(&self) -> Box<dyn SoundPlayer> {
+        if self.enabled {
+            Box::new(SystemSoundPlayer::default())
+        } else {
+            Box::new(MutedSoundPlayer)
+        }
+    }
+}
+
+pub trait SoundPlayer {
+    /// Starts playing the sound without waiting for it to finish
+    fn play(&mut self, source: &SoundSource, volume: f32) -> Result<(), String>;
+}
+
+/// Used while sound alerts are turned off
+pub struct MutedSoundPlayer;
+
+impl SoundPlayer for MutedSoundPlayer {
+    fn play(&mut self, _source: &SoundSource, _volume: f32) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Scales the samples of a 16-bit PCM WAV file, returning `None` for other formats
+fn scale_wav(bytes: &[u8], volume: f32) -> Option<Vec<u8>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut scaled = bytes.to_vec();
+    let mut is_pcm16 = false;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let start = offset + 8;
+        let end = (start + size).min(bytes.len());
+        if id == b"fmt " && size >= 16 {
+            // A truncated file is played as it is
+            let format = u16::from_le_bytes(bytes.get(start..start + 2)?.try_into().ok()?);
+            let bits = u16::from_le_bytes(bytes.get(start + 14..start + 16)?.try_into().ok()?);
+            is_pcm16 = format == 1 && bits == 16;
+        } else if id == b"data" {
+            if !is_pcm16 {
+                return None;
+            }
+            for sample in scaled[start..end].chunks_exact_mut(2) {
+                let value = i16::from_le_bytes([sample[0], sample[1]]) as f32 * volume;
+                sample.copy_from_slice(&(value as i16).to_le_bytes());
+            }
+            return Some(scaled);
+        }
+        offset = start + size + size % 2;
+    }
+    None
+}
+
+/// Where the scaled copy of a sound is written for its `play`th play, a new file each time
+/// so sounds playing at the same time don't overwrite each other's copy
+fn copy_path(name: &str, play: u64) -> PathBuf {
+    std::env::temp_dir().join(format!("daily_planner_{}_{}_{}.wav", std::process::id(), name, play))
+}
+
+/// The path as a single-quoted PowerShell string, where a quote is written twice.
+/// PowerShell takes the typographic single quotes as quotes too.
+fn powershell_quoted(path: &Path) -> String {
+    let mut quoted = String::from("'");
+    for character in path.display().to_string().chars() {
+        if ['\'', '\u{2018}', '\u{2019}', '\u{201a}', '\u{201b}'].contains(&character) {
+            quoted.push(character);
+        }
+        quoted.push(character);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Plays sounds through the command line player of the platform
+#[derive(Default)]
+pub struct SystemSoundPlayer {
+    /// Sounds played so far, numbering their copies
+    plays: u64,
+}
+
+impl SystemSoundPlayer {
+    fn commands(path: &Path, volume: f32) -> Vec<Command> {
+        let mut commands = Vec::new();
+        if cfg!(target_os = "windows") {
+            let mut command = Command::new("powershell");
+            command.args([
+                "-NoProfile",
+                "-Command",
+                &format!("(New-Object Media.SoundPlayer {}).PlaySync()", powershell_quoted(path)),
+            ]);
+            commands.push(command);
+        } else if cfg!(target_os = "macos") {
+            let mut command = Command::new("afplay");
+            command.arg("-v").arg(volume.to_string()).arg(path);
+            commands.push(command);
+        } else {
+            let mut command = Command::new("paplay");
+            command.arg(format!("--volume={}", (volume * 65536.0) as u32)).arg(path);
+            commands.push(command);
+            let mut command = Command::new("aplay");
+            command.arg("-q").arg(path);
+            commands.push(command);
+        }
+        commands
+    }
+}
+
+impl SoundPlayer for SystemSoundPlayer {
+    fn play(&mut self, source: &SoundSource, volume: f32) -> Result<(), String> {
+        let (bytes, name) = match source {
+            SoundSource::Bundled(NotificationEvent::Start) => (START_SOUND.to_vec(), "start"),
+            SoundSource::Bundled(NotificationEvent::End) => (END_SOUND.to_vec(), "end"),
+            SoundSource::Bundled(NotificationEvent::Reminder) => (REMINDER_SOUND.to_vec(), "reminder"),
+            SoundSource::File(path) => (std::fs::read(path).map_err(|error| error.to_string())?, "custom"),
+        };
+
+        // WAV files are scaled here, so the player runs at full volume.
+        // Anything else is handed over as is, relying on the player's volume option.
+        let (path, player_volume, is_copy) = match scale_wav(&bytes, volume) {
+            Some(scaled) => {
+                self.plays += 1;
+                let path = copy_path(name, self.plays);
+                std::fs::write(&path, scaled).map_err(|error| error.to_string())?;
+                (path, 1.0, true)
+            }
+            None => match source {
+                SoundSource::File(path) => (path.clone(), volume, false),
+                SoundSource::Bundled(_) => return Err("Invalid bundled sound".to_string()),
+            },
+        };
+
+        let mut errors = Vec::new();
+        for mut command in Self::commands(&path, player_volume) {
+            match command.stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+                Ok(mut child) => {
+                    // Reap the player once it is done, along with the copy it played
+                    std::thread::spawn(move || {
+                        let _ = child.wait();
+                        if is_copy {
+                            let _ = std::fs::remove_file(&path);
+                        }
+                    });
+                    return Ok(());
+                }
+                Err(error) => errors.push(error.to_string()),
+            }
+        }
+        if is_copy {
+            let _ = std::fs::remove_file(&path);
+        }
+        Err(errors.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit PCM WAV file holding `samples`
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, mono, 8000 Hz, 16000 bytes per second, 2 bytes per frame, 16 bits
+        for field in [1u16, 1] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&16000u32.to_le_bytes());
+        for field in [2u16, 16] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn wav_samples_are_scaled() {
+        let scaled = scale_wav(&wav(&[1000, -1000]), 0.5).unwrap();
+        assert_eq!(scaled, wav(&[500, -500]));
+    }
+
+    #[test]
+    fn truncated_wav_is_not_scaled() {
+        let bytes = wav(&[1000]);
+        // Cut inside the format chunk, before the bits per sample
+        assert_eq!(scale_wav(&bytes[..30], 0.5), None);
+        assert_eq!(scale_wav(b"RIFF", 0.5), None);
+    }
+
+    #[test]
+    fn each_play_gets_its_own_copy() {
+        assert_ne!(copy_path("start", 1), copy_path("start", 2));
+        assert_ne!(copy_path("start", 1), copy_path("end", 1));
+    }
+
+    #[test]
+    fn category_sounds_come_first() {
+        let mut settings = SoundSettings {
+            start: Some(PathBuf::from("start.wav")),
+            ..Default::default()
+        };
+        settings.categories.insert("work".to_string(), PathBuf::from("work.wav"));
+
+        assert_eq!(settings.source(NotificationEvent::Start, "work"), SoundSource::File(PathBuf::from("work.wav")));
+        assert_eq!(settings.source(NotificationEvent::End, "work"), SoundSource::File(PathBuf::from("work.wav")));
+        assert_eq!(settings.source(NotificationEvent::Start, ""), SoundSource::File(PathBuf::from("start.wav")));
+        assert_eq!(settings.source(NotificationEvent::Start, "health"), SoundSource::File(PathBuf::from("start.wav")));
+        assert_eq!(settings.source(NotificationEvent::End, ""), SoundSource::Bundled(NotificationEvent::End));
+    }
+
+    #[test]
+    fn quotes_in_paths_stay_in_the_powershell_string() {
+        let path = Path::new("C:\\Users\\O'Brien\\chime.wav");
+        assert_eq!(powershell_quoted(path), "'C:\\Users\\O''Brien\\chime.wav'");
+        assert_eq!(powershell_quoted(Path::new("O\u{2019}Brien.wav")), "'O\u{2019}\u{2019}Brien.wav'");
+    }
+}
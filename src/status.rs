@@ -0,0 +1,164 @@
+use chrono::Duration;
+use super::clock::Clock;
+use super::history::History;
+use super::holidays::Holidays;
+use super::structs::*;
+use super::time_format::TimeFormat;
+
+/// The activity running now
+pub struct Current {
+    pub name: String,
+    pub seconds_left: u32,
+    /// How much of it has passed, from 0 to 1
+    pub progress: f32,
+}
+
+/// The next activity to start, today or on the next plan day
+pub struct Next {
+    pub name: String,
+    pub seconds_until: u32,
+}
+
+/// What's happening now and what's coming up, for the status panel and the window title
+pub struct Status {
+    pub current: Option<Current>,
+    pub next: Option<Next>,
+}
+
+impl Status {
+    pub fn new(activities: &[Activity], clock: &dyn Clock, holidays: &Holidays, history: &History) -> Self {
+        let now = clock.now_utc();
+        let today = clock.today();
+        let tomorrow = today.succ_opt().unwrap_or(today);
+
+        let current = activities
+            .iter()
+            .filter(|activity| activity.is_now)
+            .filter_map(|activity| Some((activity, activity.span(clock, today, history)?)))
+            .min_by_key(|(_, (start, _))| *start)
+            .map(|(activity, (start, end))| {
+                // Activities run up to and including their last second
+                let end = end + Duration::seconds(1);
+                let length = (end - start).num_seconds().max(1) as f32;
+                Current {
+                    name: activity.name().to_string(),
+                    seconds_left: (end - now).num_seconds().max(0) as u32,
+                    progress: ((now - start).num_seconds() as f32 / length).clamp(0.0, 1.0),
+                }
+            });
+
+        let next = [today, tomorrow]
+            .into_iter()
+            .flat_map(|date| {
+                activities
+                    .iter()
+                    .filter(move |activity| activity.occurs_on(date, holidays))
+                    .filter_map(move |activity| Some((activity, activity.span(clock, date, history)?.0)))
+            })
+            .filter(|(_, start)| *start > now)
+            .min_by_key(|(_, start)| *start)
+            .map(|(activity, start)| Next {
+                name: activity.name().to_string(),
+                seconds_until: (start - now).num_seconds() as u32,
+            });
+
+        Status { current, next }
+    }
+
+    /// "DEEP WORK · 25 min left", or what's next when nothing is running
+    pub fn title(&self, format: &TimeFormat) -> String {
+        match (&self.current, &self.next) {
+            (Some(current), _) => format!("{} · {}", current.name, left(current, format)),
+            (None, Some(next)) => format!("Next: {} {}", next.name, starts_in(next, format)),
+            (None, None) => "DailyPlanner".to_string(),
+        }
+    }
+}
+
+/// "25 min left", counting a minute that has started as a whole one
+pub fn left(current: &Current, format: &TimeFormat) -> String {
+    format!("{} left", format.duration(current.seconds_left.div_ceil(60) * 60))
+}
+
+/// "starts in 12 min", counting a minute that has started as a whole one
+pub fn starts_in(next: &Next, format: &TimeFormat) -> String {
+    format!("starts in {}", format.duration(next.seconds_until.div_ceil(60) * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::*;
+    use super::super::time_format::HourCycle;
+    use super::super::time_zone::HomeClock;
+
+    fn status_at(activities: &mut [Activity], day: u32, hour: u32, minute: u32, second: u32, day_start: SimpleTime) -> Status {
+        let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, day, hour, minute, second).unwrap(), chrono_tz::UTC, day_start);
+        for activity in activities.iter_mut() {
+            activity.update_is_now(&clock, &Holidays::default(), &History::default());
+        }
+        Status::new(activities, &clock, &Holidays::default(), &History::default())
+    }
+
+    fn plan() -> Vec<Activity> {
+        vec![
+            Activity::new(0, "DEEP WORK".to_string(), SimpleTime::new(9, 0, 0), SimpleTime::new(10, 0, 0)),
+            Activity::new(1, "LUNCH".to_string(), SimpleTime::new(12, 0, 0), SimpleTime::new(13, 0, 0)),
+        ]
+    }
+
+    #[test]
+    fn activity_running_now() {
+        let status = status_at(&mut plan(), 19, 9, 35, 30, SimpleTime::new(0, 0, 0));
+        let current = status.current.as_ref().unwrap();
+        assert_eq!(current.name, "DEEP WORK");
+        // Up to and including 09:59:59
+        assert_eq!(current.seconds_left, 24 * 60 + 31);
+        assert!((current.progress - 2130.0 / 3601.0).abs() < 1e-6);
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        assert_eq!(left(current, &format), format!("{} left", format.duration(25 * 60)));
+
+        let next = status.next.as_ref().unwrap();
+        assert_eq!((next.name.as_str(), next.seconds_until), ("LUNCH", 2 * 3600 + 24 * 60 + 30));
+        assert_eq!(status.title(&format), format!("DEEP WORK · {}", left(current, &format)));
+    }
+
+    #[test]
+    fn between_activities() {
+        let status = status_at(&mut plan(), 19, 10, 30, 0, SimpleTime::new(0, 0, 0));
+        assert!(status.current.is_none());
+        let next = status.next.as_ref().unwrap();
+        assert_eq!((next.name.as_str(), next.seconds_until), ("LUNCH", 90 * 60));
+        let format = TimeFormat::new(HourCycle::TwentyFourHour);
+        assert_eq!(starts_in(next, &format), format!("starts in {}", format.duration(90 * 60)));
+        assert_eq!(status.title(&format), format!("Next: LUNCH {}", starts_in(next, &format)));
+    }
+
+    #[test]
+    fn after_the_last_activity() {
+        let status = status_at(&mut plan(), 19, 13, 0, 1, SimpleTime::new(0, 0, 0));
+        assert!(status.current.is_none());
+        let next = status.next.as_ref().unwrap();
+        // Tomorrow morning
+        assert_eq!((next.name.as_str(), next.seconds_until), ("DEEP WORK", 20 * 3600 - 1));
+
+        let status = status_at(&mut [], 19, 13, 0, 0, SimpleTime::new(0, 0, 0));
+        assert!(status.current.is_none() && status.next.is_none());
+        assert_eq!(status.title(&TimeFormat::new(HourCycle::TwentyFourHour)), "DailyPlanner");
+    }
+
+    #[test]
+    fn night_after_a_later_day_start() {
+        let mut activities = vec![
+            Activity::new(0, "NIGHT SHIFT".to_string(), SimpleTime::new(23, 0, 0), SimpleTime::new(2, 0, 0)),
+            Activity::new(1, "BREAKFAST".to_string(), SimpleTime::new(6, 0, 0), SimpleTime::new(7, 0, 0)),
+        ];
+        // 01:00 on the 20th is still the plan day of the 19th
+        let status = status_at(&mut activities, 20, 1, 0, 0, SimpleTime::new(5, 0, 0));
+        let current = status.current.as_ref().unwrap();
+        assert_eq!((current.name.as_str(), current.seconds_left), ("NIGHT SHIFT", 3601));
+        assert!((current.progress - 7200.0 / 10801.0).abs() < 1e-6);
+        let next = status.next.as_ref().unwrap();
+        assert_eq!((next.name.as_str(), next.seconds_until), ("BREAKFAST", 5 * 3600));
+    }
+}
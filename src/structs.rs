@@ -1,7 +1,30 @@
-use chrono::Timelike;
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc, Weekday};
 use serde::{Serialize, Deserialize};
+use super::clock::Clock;
+use super::history::History;
+use super::holidays::{HolidayRule, Holidays};
+use super::time_format::TimeFormat;
 
-#[derive(Serialize, Deserialize)]
+pub const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+/// Length given to activities added or imported with only a start time
+pub const DEFAULT_DURATION_SECONDS: u32 = 3600;
+
+/// Why an activity can't run past the end of a plan day starting at `day_start`
+pub fn ends_after_day_end(day_start: &SimpleTime, format: &TimeFormat) -> String {
+    if day_start.as_seconds() == 0 {
+        "Ends after midnight".to_string()
+    } else {
+        format!("Ends after the day ends at {}", format.time(day_start))
+    }
+}
+
+/// The first date from `date` on that falls on `weekday`
+pub fn next_date(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead = (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+    date + chrono::Duration::days(days_ahead as i64)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct SimpleTime {
     hour: u8,
     minute: u8,
@@ -17,8 +40,8 @@ impl SimpleTime {
         }
     }
 
-    pub fn from_now() -> Self {
-        let now = chrono::Local::now();
+    pub fn from_clock(clock: &dyn Clock) -> Self {
+        let now = clock.now();
         SimpleTime {
             hour: now.hour() as u8,
             minute: now.minute() as u8,
@@ -26,6 +49,16 @@ impl SimpleTime {
         }
     }
 
+    /// Builds a time from seconds since midnight, saturating at 23:59:59
+    pub fn from_seconds(seconds: u32) -> Self {
+        let seconds = seconds.min(86399);
+        SimpleTime {
+            hour: (seconds / 3600) as u8,
+            minute: (seconds % 3600 / 60) as u8,
+            seconds: (seconds % 60) as u8,
+        }
+    }
+
     pub fn hour(&self) -> u8 {
         self.hour
     }
@@ -34,19 +67,122 @@ impl SimpleTime {
         self.minute
     }
 
+    pub fn second(&self) -> u8 {
+        self.seconds
+    }
+
     pub fn as_seconds(&self) -> u32 {
         self.hour as u32 * 3600 + self.minute as u32 * 60 + self.seconds as u32
     }
 
+    /// Seconds since `day_start`, so times after midnight come after the evening on a plan day
+    /// that runs into the night
+    pub fn seconds_into_day(&self, day_start: &SimpleTime) -> u32 {
+        (self.as_seconds() + SECONDS_PER_DAY - day_start.as_seconds()) % SECONDS_PER_DAY
+    }
+
+    /// Parses "9:30", "09:30" or "09:30:15"
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().split(':');
+        let hour: u8 = parts.next()?.trim().parse().ok()?;
+        let minute: u8 = parts.next()?.trim().parse().ok()?;
+        let seconds: u8 = match parts.next() {
+            Some(part) => part.trim().parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() || hour > 23 || minute > 59 || seconds > 59 {
+            return None;
+        }
+        Some(SimpleTime::new(hour, minute, seconds))
+    }
+}
+
+impl std::fmt::Display for SimpleTime {
+    /// Formats as "HH:MM", adding the seconds only when they are set
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.seconds == 0 {
+            write!(f, "{:02}:{:02}", self.hour, self.minute)
+        } else {
+            write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.seconds)
+        }
+    }
+}
+
+/// How often the start notification of an insistent activity is repeated
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Insistence {
+    pub every_minutes: u32,
+    pub max_repeats: u32,
+}
+
+impl Default for Insistence {
+    fn default() -> Self {
+        Insistence {
+            every_minutes: 5,
+            max_repeats: 3,
+        }
+    }
+}
+
+pub const WORKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+pub const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+/// Which days an activity happens on
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Days {
+    #[default]
+    Every,
+    /// Every week on these days
+    Weekdays(Vec<Weekday>),
+    /// Once, on this date
+    Date(NaiveDate),
+}
+
+impl Days {
+    pub fn is_every(&self) -> bool {
+        *self == Days::Every
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match self {
+            Days::Every => true,
+            Days::Weekdays(weekdays) => weekdays.contains(&date.weekday()),
+            Days::Date(day) => *day == date,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Activity {
+    #[serde(default)]
     pub id: u32,
     pub name: String,
     pub start_time: SimpleTime,
     pub end_time: SimpleTime,
+    #[serde(default)]
     pub is_now: bool,
+    #[serde(default)]
+    pub notes: String,
+    /// Free-form group such as "work" or "sleep", used to pick sounds
+    #[serde(default)]
+    pub category: String,
+    /// Holds back this activity's notifications like quiet hours do
+    #[serde(default)]
+    pub silent: bool,
+    /// Minutes of warning before the start, `None` to use the global default
+    #[serde(default)]
+    pub remind_before_start: Option<u32>,
+    /// Minutes of warning before the end, `None` to use the global default
+    #[serde(default)]
+    pub remind_before_end: Option<u32>,
+    /// Repeats the start notification until it's acknowledged or the activity is done
+    #[serde(default)]
+    pub insistent: Option<Insistence>,
+    #[serde(default, skip_serializing_if = "Days::is_every")]
+    pub days: Days,
+    #[serde(default, skip_serializing_if = "HolidayRule::is_run")]
+    pub on_holidays: HolidayRule,
 }
 
 impl Activity {
@@ -62,27 +198,51 @@ impl Activity {
             start_time,
             end_time,
             is_now: false,
+            notes: String::new(),
+            category: String::new(),
+            silent: false,
+            remind_before_start: None,
+            remind_before_end: None,
+            insistent: None,
+            days: Days::Every,
+            on_holidays: HolidayRule::Run,
         }
     }
 
-    pub fn is_now(&self, current_time: SimpleTime) -> bool {
-        self.start_time.as_seconds() <= current_time.as_seconds()
-            && self.end_time.as_seconds() >= current_time.as_seconds()
+    pub fn occurs_on(&self, date: NaiveDate, holidays: &Holidays) -> bool {
+        self.days.contains(date) && self.on_holidays.allows(holidays.contains(date))
     }
 
-    /// Returns true if the activity's `is_now` field changed
-    pub fn update_is_now(&mut self) -> bool {
-        let current_time = SimpleTime::from_now();
-        let previous_is_now = self.is_now;
-        let new_is_now = self.is_now(current_time);
-        self.is_now = new_is_now;
-        if previous_is_now != new_is_now {
-            true
+    /// When the activity starts and ends on the plan day `date`, including any extension on that day,
+    /// or `None` when the clocks going forward skip all of it
+    pub fn span(&self, clock: &dyn Clock, date: NaiveDate, history: &History) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = clock.plan_instant(date, &self.start_time);
+        let end = clock.plan_instant(date, &self.end_time);
+        if start == end && self.start_time != self.end_time {
+            None
         } else {
-            false
+            Some((start, end + chrono::Duration::seconds(history.extension(date, self.id) as i64)))
         }
     }
 
+    /// Seconds from `day_start` to the start and to the end of the activity on its plan day
+    pub fn day_seconds(&self, day_start: &SimpleTime) -> (u32, u32) {
+        (self.start_time.seconds_into_day(day_start), self.end_time.seconds_into_day(day_start))
+    }
+
+    /// Returns true if the activity's `is_now` field changed.
+    /// Activities run up to and including their last second.
+    pub fn update_is_now(&mut self, clock: &dyn Clock, holidays: &Holidays, history: &History) -> bool {
+        let previous_is_now = self.is_now;
+        let now = clock.now_utc();
+        let today = clock.today();
+        let new_is_now = self.occurs_on(today, holidays) && self
+            .span(clock, today, history)
+            .is_some_and(|(start, end)| start <= now && now < end + chrono::Duration::seconds(1));
+        self.is_now = new_is_now;
+        previous_is_now != new_is_now
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -94,4 +254,129 @@ impl Activity {
     pub fn end_time(&self) -> &SimpleTime {
         &self.end_time
     }
-}
\ No newline at end of file
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use chrono::TimeZone;
+    use super::*;
+    use super::super::time_zone::HomeClock;
+
+    /// A clock stopped at a UTC time, with plans in UTC
+    fn clock_at(hour: u32, minute: u32, second: u32) -> HomeClock {
+        HomeClock::fixed(Utc.with_ymd_and_hms(2026, 10, 19, hour, minute, second).unwrap(), chrono_tz::UTC, SimpleTime::new(0, 0, 0))
+    }
+
+    fn deep_work() -> Activity {
+        Activity::new(0, "DEEP WORK".to_string(), SimpleTime::new(9, 0, 0), SimpleTime::new(10, 0, 0))
+    }
+
+    #[test]
+    fn update_is_now_follows_the_clock() {
+        let holidays = Holidays::default();
+        let mut activity = deep_work();
+        assert!(!activity.update_is_now(&clock_at(8, 59, 59), &holidays, &History::default()));
+        assert!(activity.update_is_now(&clock_at(9, 0, 0), &holidays, &History::default()));
+        assert!(activity.is_now);
+        // Still running during its last second
+        assert!(!activity.update_is_now(&clock_at(10, 0, 0), &holidays, &History::default()));
+        assert!(activity.update_is_now(&clock_at(10, 0, 1), &holidays, &History::default()));
+        assert!(!activity.is_now);
+    }
+
+    #[test]
+    fn update_is_now_skips_other_days() {
+        let mut activity = deep_work();
+        // 2026-10-19 is a Monday
+        activity.days = Days::Weekdays(vec![Weekday::Tue]);
+        activity.update_is_now(&clock_at(9, 30, 0), &Holidays::default(), &History::default());
+        assert!(!activity.is_now);
+    }
+
+    #[test]
+    fn extension_only_lasts_its_day() {
+        let holidays = Holidays::default();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let mut history = History::default();
+        let mut activity = deep_work();
+        history.extend(today, activity.id, 15 * 60);
+        activity.update_is_now(&clock_at(10, 10, 0), &holidays, &history);
+        assert!(activity.is_now);
+        activity.update_is_now(&clock_at(10, 15, 1), &holidays, &history);
+        assert!(!activity.is_now);
+        assert_eq!(activity.end_time, SimpleTime::new(10, 0, 0));
+
+        let mut history = History::default();
+        history.extend(today.pred_opt().unwrap(), activity.id, 15 * 60);
+        activity.update_is_now(&clock_at(10, 10, 0), &holidays, &history);
+        assert!(!activity.is_now);
+    }
+
+    /// A clock stopped at a UTC time, with plans in Berlin
+    fn berlin_at(month: u32, day: u32, hour: u32, minute: u32) -> HomeClock {
+        HomeClock::fixed(Utc.with_ymd_and_hms(2026, month, day, hour, minute, 0).unwrap(), chrono_tz::Europe::Berlin, SimpleTime::new(0, 0, 0))
+    }
+
+    fn activity(start: SimpleTime, end: SimpleTime) -> Activity {
+        Activity::new(0, "NIGHT SHIFT".to_string(), start, end)
+    }
+
+    #[test]
+    fn activity_in_the_skipped_hour_is_skipped() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        let mut skipped = activity(SimpleTime::new(2, 0, 0), SimpleTime::new(2, 45, 0));
+        assert_eq!(skipped.span(&berlin_at(3, 29, 0, 0), date, &History::default()), None);
+        for (hour, minute) in [(0, 59), (1, 0), (1, 10)] {
+            skipped.update_is_now(&berlin_at(3, 29, hour, minute), &Holidays::default(), &History::default());
+            assert!(!skipped.is_now);
+        }
+    }
+
+    #[test]
+    fn activity_partly_in_the_skipped_hour_starts_at_the_end_of_the_gap() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        let mut shifted = activity(SimpleTime::new(2, 30, 0), SimpleTime::new(3, 30, 0));
+        assert_eq!(
+            shifted.span(&berlin_at(3, 29, 0, 0), date, &History::default()),
+            Some((Utc.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap(), Utc.with_ymd_and_hms(2026, 3, 29, 1, 30, 0).unwrap())),
+        );
+        shifted.update_is_now(&berlin_at(3, 29, 0, 59), &Holidays::default(), &History::default());
+        assert!(!shifted.is_now);
+        shifted.update_is_now(&berlin_at(3, 29, 1, 10), &Holidays::default(), &History::default());
+        assert!(shifted.is_now);
+    }
+
+    #[test]
+    fn activity_in_the_repeated_hour_runs_once() {
+        let mut repeated = activity(SimpleTime::new(2, 15, 0), SimpleTime::new(2, 45, 0));
+        // 02:30 summer time
+        assert!(repeated.update_is_now(&berlin_at(10, 25, 0, 30), &Holidays::default(), &History::default()));
+        // 02:30 again, in winter time
+        assert!(repeated.update_is_now(&berlin_at(10, 25, 1, 30), &Holidays::default(), &History::default()));
+        assert!(!repeated.is_now);
+    }
+
+    #[test]
+    fn update_is_now_follows_the_holiday_rule() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let day_off = Holidays::load(&[], &BTreeSet::from([today]));
+        let mut activity = deep_work();
+        activity.on_holidays = HolidayRule::Skip;
+        activity.update_is_now(&clock_at(9, 30, 0), &day_off, &History::default());
+        assert!(!activity.is_now);
+        activity.on_holidays = HolidayRule::Only;
+        activity.update_is_now(&clock_at(9, 30, 0), &day_off, &History::default());
+        assert!(activity.is_now);
+        activity.update_is_now(&clock_at(9, 30, 0), &Holidays::default(), &History::default());
+        assert!(!activity.is_now);
+    }
+}
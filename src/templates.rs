@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use super::holidays::Holidays;
+use super::notifier::{Notification, NotificationEvent};
+use super::reminders::ReminderKind;
+use super::structs::*;
+use super::time_zone::LocalTimes;
+
+/// Placeholders understood by `render`, shown as a hint in the settings
+pub const PLACEHOLDERS: [&str; 9] = [
+    "{name}", "{start}", "{end}", "{duration}", "{next_name}", "{next_start}", "{notes}", "{category}", "{minutes}",
+];
+
+/// Which activity notification a template is for
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TemplateKind {
+    Start,
+    End,
+    BeforeStart,
+    BeforeEnd,
+    /// Repeated start of an insistent activity
+    Repeat,
+}
+
+impl TemplateKind {
+    pub const ALL: [TemplateKind; 5] = [
+        TemplateKind::Start,
+        TemplateKind::End,
+        TemplateKind::BeforeStart,
+        TemplateKind::BeforeEnd,
+        TemplateKind::Repeat,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemplateKind::Start => "Start",
+            TemplateKind::End => "End",
+            TemplateKind::BeforeStart => "Reminder before start",
+            TemplateKind::BeforeEnd => "Reminder before end",
+            TemplateKind::Repeat => "Repeat until acknowledged",
+        }
+    }
+
+    pub fn reminder(kind: ReminderKind) -> Self {
+        match kind {
+            ReminderKind::BeforeStart => TemplateKind::BeforeStart,
+            ReminderKind::BeforeEnd => TemplateKind::BeforeEnd,
+        }
+    }
+
+    pub fn event(&self) -> NotificationEvent {
+        match self {
+            TemplateKind::Start => NotificationEvent::Start,
+            TemplateKind::End => NotificationEvent::End,
+            TemplateKind::BeforeStart | TemplateKind::BeforeEnd | TemplateKind::Repeat => NotificationEvent::Reminder,
+        }
+    }
+
+    pub fn default_template(&self) -> Template {
+        let (summary, body) = match self {
+            TemplateKind::Start => ("Activity Started", "{name} has started."),
+            TemplateKind::End => ("Activity Ended", "{name} has ended."),
+            TemplateKind::BeforeStart => ("Activity Starting Soon", "{name} starts in {minutes} min."),
+            TemplateKind::BeforeEnd => ("Activity Ending Soon", "{name} ends in {minutes} min."),
+            TemplateKind::Repeat => ("Still Waiting", "{name} started {minutes} min ago."),
+        };
+        Template { summary: summary.to_string(), body: body.to_string() }
+    }
+}
+
+/// The plan day a notification is sent on, to tell which activity comes next
+pub struct PlanDay<'a> {
+    pub date: NaiveDate,
+    pub day_start: SimpleTime,
+    pub holidays: &'a Holidays,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Template {
+    pub summary: String,
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct NotificationTemplates {
+    /// Templates replacing the default ones
+    pub events: BTreeMap<TemplateKind, Template>,
+    /// Templates used for activities in a category
+    pub categories: BTreeMap<String, BTreeMap<TemplateKind, Template>>,
+}
+
+impl NotificationTemplates {
+    /// Picks the category's template first, then the customized one, then the default
+    pub fn template(&self, kind: TemplateKind, category: &str) -> Template {
+        self.categories
+            .get(category)
+            .and_then(|templates| templates.get(&kind))
+            .or(self.events.get(&kind))
+            .cloned()
+            .unwrap_or_else(|| kind.default_template())
+    }
+
+    /// Templates edited for `category`, or the event templates for an empty category
+    pub fn scope_mut(&mut self, category: &str) -> &mut BTreeMap<TemplateKind, Template> {
+        if category.is_empty() {
+            &mut self.events
+        } else {
+            self.categories.entry(category.to_string()).or_default()
+        }
+    }
+
+    /// Whether `category`, or all activities for an empty category, has its own template
+    pub fn is_customized(&self, kind: TemplateKind, category: &str) -> bool {
+        if category.is_empty() {
+            self.events.contains_key(&kind)
+        } else {
+            self.categories.get(category).is_some_and(|templates| templates.contains_key(&kind))
+        }
+    }
+
+    /// Fills in the activity's template, with `minutes` left until the event for reminders,
+    /// or since the start for repeats
+    pub fn notification(
+        &self,
+        kind: TemplateKind,
+        activity: &Activity,
+        activities: &[Activity],
+        day: &PlanDay,
+        minutes: u32,
+        local_times: &LocalTimes,
+    ) -> Notification {
+        let template = self.template(kind, activity.category());
+        let next = next_activity(activity, activities, day);
+        Notification::new(
+            &render(&template.summary, activity, next, minutes, local_times),
+            &render(&template.body, activity, next, minutes, local_times),
+        )
+        .for_activity(activity.id, day.date, kind.event())
+    }
+}
+
+/// The activity starting soonest after `activity` starts, among those happening on the plan day
+fn next_activity<'a>(activity: &Activity, activities: &'a [Activity], day: &PlanDay) -> Option<&'a Activity> {
+    let start = |activity: &Activity| activity.start_time().seconds_into_day(&day.day_start);
+    activities
+        .iter()
+        .filter(|other| other.occurs_on(day.date, day.holidays))
+        .filter(|other| start(other) > start(activity))
+        .min_by_key(|other| start(other))
+}
+
+/// Replaces the placeholders in `text`, leaving unknown ones as they are.
+/// Without a next activity, its placeholders are left empty. Times are shown as local times.
+pub fn render(text: &str, activity: &Activity, next: Option<&Activity>, minutes: u32, local_times: &LocalTimes) -> String {
+    // Counted round midnight for activities running into the night
+    let duration = activity.end_time().seconds_into_day(activity.start_time());
+    let values = [
+        ("{name}", activity.name().to_string()),
+        ("{start}", local_times.show(activity.start_time())),
+        ("{end}", local_times.show(activity.end_time())),
+        ("{duration}", local_times.format().duration(duration)),
+        ("{next_name}", next.map_or(String::new(), |next| next.name().to_string())),
+        ("{next_start}", next.map_or(String::new(), |next| local_times.show(next.start_time()))),
+        ("{notes}", activity.notes().to_string()),
+        ("{category}", activity.category().to_string()),
+        ("{minutes}", minutes.to_string()),
+    ];
+
+    // A single pass, so values containing braces aren't replaced again
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest
+            .find('}')
+            .and_then(|close| values.iter().find(|(placeholder, _)| *placeholder == &rest[..=close]));
+        match value {
+            Some((placeholder, value)) => {
+                rendered.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use chrono::Weekday;
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use super::super::clock::FixedClock;
+    use super::super::holidays::HolidayRule;
+    use super::super::time_format::{HourCycle, TimeFormat};
+    use super::super::time_zone::HomeClock;
+
+    fn activity(id: u32, name: &str, hour: u8) -> Activity {
+        Activity::new(id, name.to_string(), SimpleTime::new(hour, 0, 0), SimpleTime::new(hour, 30, 0))
+    }
+
+    /// Shows plan times as they are, on a 24-hour clock
+    fn local_times() -> LocalTimes {
+        let clock = HomeClock {
+            inner: Box::new(FixedClock(Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap())),
+            home: None,
+            day_start: SimpleTime::new(0, 0, 0),
+        };
+        clock.local_times(TimeFormat::new(HourCycle::TwentyFourHour))
+    }
+
+    fn template(summary: &str, body: &str) -> Template {
+        Template { summary: summary.to_string(), body: body.to_string() }
+    }
+
+    #[test]
+    fn category_templates_come_first() {
+        let mut templates = NotificationTemplates::default();
+        assert_eq!(templates.template(TemplateKind::Start, "work"), TemplateKind::Start.default_template());
+
+        templates.scope_mut("").insert(TemplateKind::Start, template("Go", "{name}"));
+        templates.scope_mut("work").insert(TemplateKind::Start, template("Work", "{name} at {start}"));
+        assert_eq!(templates.template(TemplateKind::Start, "work").summary, "Work");
+        assert_eq!(templates.template(TemplateKind::Start, "sleep").summary, "Go");
+        assert_eq!(templates.template(TemplateKind::End, "work"), TemplateKind::End.default_template());
+        assert!(templates.is_customized(TemplateKind::Start, ""));
+        assert!(templates.is_customized(TemplateKind::Start, "work"));
+        assert!(!templates.is_customized(TemplateKind::End, "work"));
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let mut reading = Activity::new(0, "READING".to_string(), SimpleTime::new(23, 0, 0), SimpleTime::new(0, 30, 0));
+        reading.notes = "Chapter {3}".to_string();
+        reading.category = "evening".to_string();
+        let sleep = activity(1, "SLEEP", 1);
+        let local_times = local_times();
+        let duration = local_times.format().duration(90 * 60);
+
+        assert_eq!(
+            render("{name} {start}-{end} ({duration}), then {next_name} at {next_start}", &reading, Some(&sleep), 0, &local_times),
+            format!("READING 23:00-00:30 ({}), then SLEEP at 01:00", duration),
+        );
+        // Values aren't replaced again, and unknown placeholders stay
+        assert_eq!(render("{notes} {category} {unknown} {", &reading, None, 0, &local_times), "Chapter {3} evening {unknown} {");
+        assert_eq!(render("in {minutes} min{next_name}", &reading, None, 5, &local_times), "in 5 min");
+    }
+
+    #[test]
+    fn notifications_use_the_activity_category() {
+        let mut templates = NotificationTemplates::default();
+        templates.scope_mut("work").insert(TemplateKind::BeforeEnd, template("{name}", "Wrap up, {minutes} min left"));
+        let mut meeting = activity(0, "MEETING", 10);
+        meeting.category = "work".to_string();
+        let activities = [meeting];
+        let holidays = Holidays::default();
+        let day = PlanDay { date: NaiveDate::from_ymd_opt(2026, 10, 19).unwrap(), day_start: SimpleTime::new(0, 0, 0), holidays: &holidays };
+
+        let notification = templates.notification(TemplateKind::BeforeEnd, &activities[0], &activities, &day, 10, &local_times());
+        assert_eq!(notification.summary, "MEETING");
+        assert_eq!(notification.body, "Wrap up, 10 min left");
+        assert_eq!(notification.activity_id, Some(0));
+        assert_eq!(notification.date, Some(day.date));
+        assert_eq!(notification.event, Some(NotificationEvent::Reminder));
+    }
+
+    #[test]
+    fn next_activity_happens_on_the_same_day() {
+        // 2026-10-19 is a Monday
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let holidays = Holidays::default();
+        let day = PlanDay { date, day_start: SimpleTime::new(5, 0, 0), holidays: &holidays };
+        let mut tuesday = activity(1, "TUESDAY CALL", 10);
+        tuesday.days = Days::Weekdays(vec![Weekday::Tue]);
+        let mut dentist = activity(2, "DENTIST", 11);
+        dentist.days = Days::Date(date.succ_opt().unwrap());
+        let activities = [activity(0, "DEEP WORK", 9), tuesday, dentist, activity(3, "LUNCH", 12), activity(4, "LATE READ", 1)];
+
+        let next = |index: usize| next_activity(&activities[index], &activities, &day).map(|next| next.name());
+        assert_eq!(next(0), Some("LUNCH"));
+        // Past midnight still comes after the evening
+        assert_eq!(next(3), Some("LATE READ"));
+        assert_eq!(next(4), None);
+    }
+
+    #[test]
+    fn next_activity_follows_the_holiday_rule() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let mut standup = activity(1, "STANDUP", 10);
+        standup.on_holidays = HolidayRule::Skip;
+        let mut sleep_in = activity(2, "SLEEP IN", 11);
+        sleep_in.on_holidays = HolidayRule::Only;
+        let activities = [activity(0, "BREAKFAST", 8), standup, sleep_in];
+        let next = |holidays: &Holidays| {
+            let day = PlanDay { date, day_start: SimpleTime::new(0, 0, 0), holidays };
+            next_activity(&activities[0], &activities, &day).map(|next| next.name().to_string())
+        };
+
+        assert_eq!(next(&Holidays::default()), Some("STANDUP".to_string()));
+        assert_eq!(next(&Holidays::load(&[], &BTreeSet::from([date]))), Some("SLEEP IN".to_string()));
+    }
+}
@@ -0,0 +1,115 @@
+use super::structs::*;
+
+/// One non-empty line of a pasted plan, parsed or with the reason it was rejected
+pub struct ParsedLine {
+    pub text: String,
+    pub result: Result<(SimpleTime, SimpleTime, String), String>,
+}
+
+/// Reads a leading "9", "09:30" or "9.30", returning the time and the rest of the text
+fn take_time(text: &str) -> Option<(SimpleTime, &str)> {
+    let hour_digits = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if hour_digits == 0 || hour_digits > 2 {
+        return None;
+    }
+    let hour: u8 = text[..hour_digits].parse().ok()?;
+    let mut rest = &text[hour_digits..];
+
+    let mut minute = 0;
+    if let Some(after_separator) = rest.strip_prefix([':', '.']) {
+        let minute_digits = after_separator.chars().take_while(|c| c.is_ascii_digit()).count();
+        if minute_digits != 2 {
+            return None;
+        }
+        minute = after_separator[..2].parse().ok()?;
+        rest = &after_separator[2..];
+    }
+
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    Some((SimpleTime::new(hour, minute, 0), rest))
+}
+
+/// Parses lines like "09:00-10:30 Deep work" or "7:30 - 8 Breakfast".
+/// Ends after midnight are fine when the plan day `day_start` starts at runs past it.
+pub fn parse_line(line: &str, day_start: &SimpleTime) -> Result<(SimpleTime, SimpleTime, String), String> {
+    let line = line.trim();
+    let (start_time, rest) = take_time(line).ok_or("Expected a start time")?;
+    let rest = rest
+        .trim_start()
+        .strip_prefix(['-', '–'])
+        .ok_or("Expected \"-\" after the start time")?;
+    let (end_time, rest) = take_time(rest.trim_start()).ok_or("Expected an end time")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return Err("Expected a space after the end time".to_string());
+    }
+    let name = rest.trim();
+    if name.is_empty() {
+        return Err("Missing activity name".to_string());
+    }
+    if end_time.seconds_into_day(day_start) <= start_time.seconds_into_day(day_start) {
+        return Err("Ends before it starts".to_string());
+    }
+    Ok((start_time, end_time, name.to_string()))
+}
+
+/// Parses every non-empty line of a text block
+pub fn parse_text(text: &str, day_start: &SimpleTime) -> Vec<ParsedLine> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| ParsedLine {
+            text: line.trim().to_string(),
+            result: parse_line(line, day_start),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn midnight() -> SimpleTime {
+        SimpleTime::new(0, 0, 0)
+    }
+
+    #[test]
+    fn reads_times_and_names() {
+        assert_eq!(
+            parse_line("09:00-10:30 Deep work", &midnight()),
+            Ok((SimpleTime::new(9, 0, 0), SimpleTime::new(10, 30, 0), "Deep work".to_string())),
+        );
+        assert_eq!(
+            parse_line("  7.30 – 8 Breakfast  ", &midnight()),
+            Ok((SimpleTime::new(7, 30, 0), SimpleTime::new(8, 0, 0), "Breakfast".to_string())),
+        );
+    }
+
+    #[test]
+    fn rejects_bad_times() {
+        assert_eq!(parse_line("Lunch 12-13", &midnight()), Err("Expected a start time".to_string()));
+        assert_eq!(parse_line("24:00-25:00 Late", &midnight()), Err("Expected a start time".to_string()));
+        assert_eq!(parse_line("9:5-10 Typo", &midnight()), Err("Expected a start time".to_string()));
+        assert_eq!(parse_line("9 10 Lunch", &midnight()), Err("Expected \"-\" after the start time".to_string()));
+        assert_eq!(parse_line("9-10:75 Lunch", &midnight()), Err("Expected an end time".to_string()));
+        assert_eq!(parse_line("9-10Lunch", &midnight()), Err("Expected a space after the end time".to_string()));
+        assert_eq!(parse_line("9-10", &midnight()), Err("Missing activity name".to_string()));
+    }
+
+    #[test]
+    fn rejects_lines_that_end_before_they_start() {
+        assert_eq!(parse_line("10:00-09:00 Backwards", &midnight()), Err("Ends before it starts".to_string()));
+        assert_eq!(parse_line("10-10 Empty", &midnight()), Err("Ends before it starts".to_string()));
+    }
+
+    #[test]
+    fn lines_past_midnight_belong_to_a_later_day_start() {
+        let day_start = SimpleTime::new(5, 0, 0);
+        assert_eq!(
+            parse_line("23:00-01:00 Night shift", &day_start),
+            Ok((SimpleTime::new(23, 0, 0), SimpleTime::new(1, 0, 0), "Night shift".to_string())),
+        );
+        assert_eq!(parse_line("23:00-01:00 Night shift", &midnight()), Err("Ends before it starts".to_string()));
+        assert_eq!(parse_line("04:00-06:00 Across the day start", &day_start), Err("Ends before it starts".to_string()));
+    }
+}
@@ -0,0 +1,253 @@
+use chrono::{DateTime, Locale, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use serde::{Serialize, Deserialize};
+use super::structs::*;
+use super::time_input::parse_time;
+
+/// Whether times are shown on a 12-hour or a 24-hour clock
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum HourCycle {
+    /// Whatever the system locale uses
+    System,
+    TwentyFourHour,
+    TwelveHour,
+}
+
+impl HourCycle {
+    pub const ALL: [HourCycle; 3] = [HourCycle::System, HourCycle::TwentyFourHour, HourCycle::TwelveHour];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HourCycle::System => "System",
+            HourCycle::TwentyFourHour => "24-hour",
+            HourCycle::TwelveHour => "12-hour",
+        }
+    }
+}
+
+/// Short names of the units of a duration, as chrono has none
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct DurationUnits {
+    hours: &'static str,
+    minutes: &'static str,
+    /// Written between numbers and units, empty where they run together
+    space: &'static str,
+}
+
+/// Units by language, English for the languages not listed
+const DURATION_UNITS: [(&str, DurationUnits); 19] = [
+    ("en", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("cs", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("da", DurationUnits { hours: "t", minutes: "min", space: " " }),
+    ("de", DurationUnits { hours: "Std.", minutes: "Min.", space: " " }),
+    ("es", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("fi", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("fr", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("it", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("ja", DurationUnits { hours: "時間", minutes: "分", space: "" }),
+    ("ko", DurationUnits { hours: "시간", minutes: "분", space: "" }),
+    ("nb", DurationUnits { hours: "t", minutes: "min", space: " " }),
+    ("nl", DurationUnits { hours: "u", minutes: "min", space: " " }),
+    ("pl", DurationUnits { hours: "godz.", minutes: "min", space: " " }),
+    ("pt", DurationUnits { hours: "h", minutes: "min", space: " " }),
+    ("ru", DurationUnits { hours: "ч", minutes: "мин", space: " " }),
+    ("sv", DurationUnits { hours: "tim", minutes: "min", space: " " }),
+    ("tr", DurationUnits { hours: "sa", minutes: "dk", space: " " }),
+    ("uk", DurationUnits { hours: "год", minutes: "хв", space: " " }),
+    ("zh", DurationUnits { hours: "小时", minutes: "分钟", space: "" }),
+];
+
+impl DurationUnits {
+    fn for_locale(locale: Locale) -> Self {
+        let name = locale.to_string();
+        let language = name.split('_').next().unwrap_or_default();
+        DURATION_UNITS
+            .iter()
+            .find(|(units_language, _)| *units_language == language)
+            .unwrap_or(&DURATION_UNITS[0])
+            .1
+    }
+}
+
+/// Shows times, durations and dates the way the user reads them:
+/// on their preferred clock, with the system locale's names and date order
+#[derive(Clone, Copy)]
+pub struct TimeFormat {
+    locale: Locale,
+    twelve_hour: bool,
+    units: DurationUnits,
+}
+
+impl TimeFormat {
+    pub fn new(hour_cycle: HourCycle) -> Self {
+        let locale = system_locale();
+        let twelve_hour = match hour_cycle {
+            HourCycle::System => uses_twelve_hour(locale),
+            HourCycle::TwentyFourHour => false,
+            HourCycle::TwelveHour => true,
+        };
+        TimeFormat { locale, twelve_hour, units: DurationUnits::for_locale(locale) }
+    }
+
+    /// "21:30" or "9:30 PM", adding the seconds only when they are set
+    pub fn time(&self, time: &SimpleTime) -> String {
+        let seconds = time.as_seconds();
+        let (hour, minute, second) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+        let clock = if second == 0 {
+            format!("{:02}", minute)
+        } else {
+            format!("{:02}:{:02}", minute, second)
+        };
+        if self.twelve_hour {
+            format!("{}:{} {}", twelve_hour(hour), clock, self.am_pm(hour))
+        } else {
+            format!("{:02}:{}", hour, clock)
+        }
+    }
+
+    /// Time of day of a date and time
+    pub fn wall_time(&self, time: &impl Timelike) -> String {
+        self.time(&SimpleTime::from_seconds(time.num_seconds_from_midnight()))
+    }
+
+    /// A whole hour, where 24 is the midnight ending the day: "21:00" or "9 PM"
+    pub fn hour(&self, hour: u32) -> String {
+        if self.twelve_hour {
+            format!("{} {}", twelve_hour(hour % 24), self.am_pm(hour % 24))
+        } else {
+            format!("{:02}:00", hour)
+        }
+    }
+
+    /// Reads a time as `time` shows it, with the locale's AM and PM, or typed any way `parse_time` reads
+    pub fn parse(&self, text: &str) -> Option<SimpleTime> {
+        let text = text.trim();
+        if self.twelve_hour {
+            for (hour, english) in [(0, "am"), (12, "pm")] {
+                let marker = self.am_pm(hour);
+                let time = text.strip_suffix(marker.as_str()).or_else(|| text.strip_prefix(marker.as_str()));
+                if let Some(time) = time {
+                    return parse_time(&format!("{}{}", time, english));
+                }
+            }
+        }
+        parse_time(text)
+    }
+
+    /// The unit after a number of minutes, like " min", for fields holding one
+    pub fn minutes_suffix(&self) -> String {
+        format!("{}{}", self.units.space, self.units.minutes)
+    }
+
+    /// "45 min", "2 h" or "1 h 30 min", with the units of the locale's language
+    /// where they're known and in English otherwise
+    pub fn duration(&self, seconds: u32) -> String {
+        let DurationUnits { hours: hours_unit, minutes: minutes_unit, space } = self.units;
+        let minutes = seconds / 60;
+        match (minutes / 60, minutes % 60) {
+            (0, minutes) => format!("{}{}{}", minutes, space, minutes_unit),
+            (hours, 0) => format!("{}{}{}", hours, space, hours_unit),
+            (hours, minutes) => format!("{}{}{}{}{}{}{}", hours, space, hours_unit, space, minutes, space, minutes_unit),
+        }
+    }
+
+    /// The date in the locale's short form, like "10/19/26" or "19.10.2026"
+    pub fn date(&self, date: NaiveDate) -> String {
+        date.format_localized("%x", self.locale).to_string()
+    }
+
+    /// The weekday's full name, like "Monday" or "lundi"
+    pub fn weekday(&self, weekday: Weekday) -> String {
+        // Any week will do
+        let date = NaiveDate::from_isoywd_opt(2024, 1, weekday).unwrap_or_default();
+        date.format_localized("%A", self.locale).to_string()
+    }
+
+    /// "Every day", "Every Monday, Thursday" or "Once on 10/19/26"
+    pub fn days(&self, days: &Days) -> String {
+        match days {
+            Days::Every => "Every day".to_string(),
+            Days::Weekdays(weekdays) if *weekdays == WORKDAYS => "Every weekday".to_string(),
+            Days::Weekdays(weekdays) if *weekdays == WEEKEND => "Every weekend".to_string(),
+            Days::Weekdays(weekdays) => format!(
+                "Every {}",
+                weekdays.iter().map(|weekday| self.weekday(*weekday)).collect::<Vec<_>>().join(", "),
+            ),
+            Days::Date(date) => format!("Once on {}", self.date(*date)),
+        }
+    }
+
+    fn am_pm(&self, hour: u32) -> String {
+        let text = at_hour(hour).format_localized("%p", self.locale).to_string();
+        // Locales on a 24-hour clock may have no names for them
+        if text.is_empty() {
+            at_hour(hour).format("%p").to_string()
+        } else {
+            text
+        }
+    }
+}
+
+fn twelve_hour(hour: u32) -> u32 {
+    match hour % 12 {
+        0 => 12,
+        hour => hour,
+    }
+}
+
+/// Any day at `hour`, to format parts of a time that don't depend on the date
+fn at_hour(hour: u32) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap_or_default()
+        .and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or_default())
+        .and_utc()
+}
+
+/// The locale the system is set to, like "en-US" or "de_DE.UTF-8", or POSIX if it's unknown
+fn system_locale() -> Locale {
+    let Some(tag) = sys_locale::get_locale() else {
+        return Locale::POSIX;
+    };
+    let name = tag.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+    let language = name.split('_').next().unwrap_or_default();
+    // A bare language like "fr" is taken as spoken in its own country
+    Locale::try_from(name.as_str())
+        .or_else(|_| Locale::try_from(format!("{}_{}", language, language.to_uppercase()).as_str()))
+        .unwrap_or(Locale::POSIX)
+}
+
+/// Whether the locale shows 13:00 as "1" something
+fn uses_twelve_hour(locale: Locale) -> bool {
+    !at_hour(13).format_localized("%X", locale).to_string().contains("13")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(locale: Locale, twelve_hour: bool) -> TimeFormat {
+        TimeFormat { locale, twelve_hour, units: DurationUnits::for_locale(locale) }
+    }
+
+    #[test]
+    fn shown_times_read_back() {
+        for time_format in [format(Locale::en_US, true), format(Locale::ko_KR, true), format(Locale::ja_JP, true), format(Locale::de_DE, false)] {
+            for seconds in (0..SECONDS_PER_DAY).step_by(60).chain([13 * 3600 + 5 * 60 + 7]) {
+                let time = SimpleTime::from_seconds(seconds);
+                let shown = time_format.time(&time);
+                assert_eq!(time_format.parse(&shown), Some(time), "{}", shown);
+            }
+        }
+        // Typed the English way on a Korean clock too
+        assert_eq!(format(Locale::ko_KR, true).parse("9:30pm"), Some(SimpleTime::new(21, 30, 0)));
+    }
+
+    #[test]
+    fn durations_follow_the_language() {
+        assert_eq!(format(Locale::en_US, false).duration(90 * 60), "1 h 30 min");
+        assert_eq!(format(Locale::de_DE, false).duration(90 * 60), "1 Std. 30 Min.");
+        assert_eq!(format(Locale::ja_JP, false).duration(45 * 60), "45分");
+        assert_eq!(format(Locale::ko_KR, false).duration(2 * 3600), "2시간");
+        // Languages without units of their own get the English ones
+        assert_eq!(format(Locale::POSIX, false).duration(5 * 60), "5 min");
+    }
+}
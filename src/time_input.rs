@@ -0,0 +1,263 @@
+use eframe::egui;
+use serde::{Serialize, Deserialize};
+use super::color_palette::*;
+use super::structs::*;
+use super::time_format::TimeFormat;
+
+/// Minutes typed and stepped times are rounded to
+pub const SNAP_CHOICES: [u32; 3] = [1, 5, 15];
+
+/// How times are entered
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TimeEntry {
+    /// Type times in a text field, or pick them from hour and minute lists
+    pub typed: bool,
+    pub snap_minutes: u32,
+    /// Show a field for the seconds
+    pub seconds: bool,
+}
+
+impl Default for TimeEntry {
+    fn default() -> Self {
+        TimeEntry {
+            typed: true,
+            snap_minutes: 1,
+            seconds: false,
+        }
+    }
+}
+
+impl TimeEntry {
+    /// Rounds to the nearest snapping step, which drops the seconds unless it's a minute
+    pub fn snap(&self, time: SimpleTime) -> SimpleTime {
+        if self.snap_minutes <= 1 {
+            return time;
+        }
+        let step = self.snap_minutes * 60;
+        let snapped = (time.as_seconds() + step / 2) / step * step;
+        SimpleTime::from_seconds(snapped % SECONDS_PER_DAY)
+    }
+
+    /// Moves `steps` snapping steps later, or earlier when negative, going round midnight
+    fn step(&self, time: SimpleTime, steps: i32) -> SimpleTime {
+        let step = self.snap_minutes.max(1) as i32 * 60;
+        let seconds = time.as_seconds() as i32;
+        let stepped = if steps > 0 {
+            (seconds.div_euclid(step) + steps) * step
+        } else {
+            // Times between steps go back to the step they're past first
+            (seconds + step - 1).div_euclid(step) * step + steps * step
+        };
+        SimpleTime::from_seconds(stepped.rem_euclid(SECONDS_PER_DAY as i32) as u32)
+    }
+}
+
+/// Parses a typed time: "930", "09:30", "9.30pm", "9 a.m.", "21:30:15" or "213015"
+pub fn parse_time(text: &str) -> Option<SimpleTime> {
+    let text = text
+        .trim()
+        .to_lowercase()
+        .replace(' ', "")
+        .replace("a.m", "am")
+        .replace("p.m", "pm")
+        .trim_end_matches('.')
+        .to_string();
+    // Digits are picked out by position below, which only works on single-byte characters
+    if !text.is_ascii() {
+        return None;
+    }
+    let (text, pm) = if let Some(text) = text.strip_suffix("am").or(text.strip_suffix('a')) {
+        (text.to_string(), Some(false))
+    } else if let Some(text) = text.strip_suffix("pm").or(text.strip_suffix('p')) {
+        (text.to_string(), Some(true))
+    } else {
+        (text, None)
+    };
+
+    let parts: Vec<&str> = if text.contains([':', '.']) {
+        text.split([':', '.']).collect()
+    } else {
+        // Without separators the hour takes what the minutes and seconds leave
+        match text.len() {
+            1 | 2 => vec![&text[..]],
+            3 | 4 => vec![&text[..text.len() - 2], &text[text.len() - 2..]],
+            5 | 6 => vec![&text[..text.len() - 4], &text[text.len() - 4..text.len() - 2], &text[text.len() - 2..]],
+            _ => return None,
+        }
+    };
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit())) {
+        return None;
+    }
+    let mut hour: u8 = parts[0].parse().ok()?;
+    let minute: u8 = parts.get(1).map_or(Some(0), |part| part.parse().ok())?;
+    let seconds: u8 = parts.get(2).map_or(Some(0), |part| part.parse().ok())?;
+
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    if hour > 23 || minute > 59 || seconds > 59 {
+        return None;
+    }
+    Some(SimpleTime::new(hour, minute, seconds))
+}
+
+/// Edits a time, typed in a text field or picked from lists depending on `entry`.
+/// In the text field the arrow keys and the scroll wheel move it by the snapping step.
+pub fn time_picker(ui: &mut egui::Ui, time: &mut SimpleTime, id_prefix: &str, format: &TimeFormat, entry: &TimeEntry) {
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+        ui.add_space(20.0);
+
+        if entry.seconds {
+            let mut seconds = time.second();
+            ui.add(egui::DragValue::new(&mut seconds).range(0..=59).prefix(":"));
+            *time = SimpleTime::new(time.hour(), time.minute(), seconds);
+        }
+
+        if entry.typed {
+            typed_time(ui, time, id_prefix, format, entry);
+        } else {
+            time_lists(ui, time, id_prefix, format);
+        }
+    });
+}
+
+fn typed_time(ui: &mut egui::Ui, time: &mut SimpleTime, id_prefix: &str, format: &TimeFormat, entry: &TimeEntry) {
+    let id = egui::Id::new(format!("{}_time", id_prefix));
+    let focused = ui.memory(|memory| memory.has_focus(id));
+
+    let mut steps = 0;
+    if focused {
+        ui.input_mut(|input| {
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                steps += 1;
+            }
+            if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                steps -= 1;
+            }
+        });
+    }
+
+    // What's being typed is kept until the field loses focus, the time shown otherwise
+    let mut text = ui
+        .data(|data| data.get_temp::<String>(id))
+        .filter(|_| focused)
+        .unwrap_or_else(|| format.time(time));
+    let is_valid = format.parse(&text).is_some();
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut text)
+            .id(id)
+            .desired_width(90.0)
+            .text_color_opt((!is_valid).then_some(RED)),
+    );
+
+    if response.hovered() {
+        // Taken from the input so a scroll area around the field stays put
+        let scroll = ui.input_mut(|input| {
+            let scroll = input.raw_scroll_delta.y;
+            input.raw_scroll_delta = egui::Vec2::ZERO;
+            input.smooth_scroll_delta = egui::Vec2::ZERO;
+            scroll
+        });
+        if scroll > 0.0 {
+            steps += 1;
+        } else if scroll < 0.0 {
+            steps -= 1;
+        }
+    }
+
+    if steps != 0 {
+        *time = entry.step(*time, steps);
+        text = format.time(time);
+    } else if response.changed() {
+        if let Some(typed) = format.parse(&text) {
+            *time = entry.snap(typed);
+        }
+    }
+    ui.data_mut(|data| data.insert_temp(id, text));
+}
+
+/// Hour and minute lists, for those who'd rather pick than type
+fn time_lists(ui: &mut egui::Ui, time: &mut SimpleTime, id_prefix: &str, format: &TimeFormat) {
+    let (mut hour, mut minute) = (time.hour(), time.minute());
+
+    egui::ComboBox::from_id_salt(format!("{}_minute", id_prefix))
+        .width(64.0)
+        .selected_text(
+            format!("{}{}", minute, format.minutes_suffix())
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut minute, 0, "Minute".to_string());
+            for m in 0..=59 {
+                ui.selectable_value(&mut minute, m, m.to_string());
+            }
+        });
+
+    egui::ComboBox::from_id_salt(format!("{}_hour", id_prefix))
+        .width(64.0)
+        .selected_text(
+            format.hour(hour as u32)
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut hour, 0, "Hour".to_string());
+            for h in 0..=23 {
+                ui.selectable_value(&mut hour, h, format.hour(h as u32));
+            }
+        });
+
+    *time = SimpleTime::new(hour, minute, time.second());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> Option<String> {
+        parse_time(text).map(|time| time.to_string())
+    }
+
+    #[test]
+    fn parses_typed_times() {
+        assert_eq!(parsed("930"), Some("09:30".to_string()));
+        assert_eq!(parsed("9:30"), Some("09:30".to_string()));
+        assert_eq!(parsed("09:30"), Some("09:30".to_string()));
+        assert_eq!(parsed("9.30pm"), Some("21:30".to_string()));
+        assert_eq!(parsed("9 a.m."), Some("09:00".to_string()));
+        assert_eq!(parsed("12am"), Some("00:00".to_string()));
+        assert_eq!(parsed("12pm"), Some("12:00".to_string()));
+        assert_eq!(parsed("213015"), Some("21:30:15".to_string()));
+        assert_eq!(parsed("21:30:15"), Some("21:30:15".to_string()));
+    }
+
+    #[test]
+    fn rejects_out_of_range_times() {
+        assert_eq!(parsed("2400"), None);
+        assert_eq!(parsed("9:60"), None);
+        assert_eq!(parsed("9:30:60"), None);
+        assert_eq!(parsed("13pm"), None);
+        assert_eq!(parsed("0am"), None);
+        assert_eq!(parsed("1234567"), None);
+        assert_eq!(parsed(""), None);
+    }
+
+    #[test]
+    fn rejects_non_ascii_text() {
+        assert_eq!(parsed("é1"), None);
+        assert_eq!(parsed("１２"), None);
+        assert_eq!(parsed("１２:３０"), None);
+        assert_eq!(parsed("élan"), None);
+    }
+
+    #[test]
+    fn snaps_and_steps() {
+        let entry = TimeEntry { snap_minutes: 15, ..Default::default() };
+        assert_eq!(entry.snap(SimpleTime::new(9, 7, 0)), SimpleTime::new(9, 0, 0));
+        assert_eq!(entry.snap(SimpleTime::new(9, 8, 0)), SimpleTime::new(9, 15, 0));
+        assert_eq!(entry.step(SimpleTime::new(9, 7, 0), 1), SimpleTime::new(9, 15, 0));
+        assert_eq!(entry.step(SimpleTime::new(9, 7, 0), -1), SimpleTime::new(9, 0, 0));
+        assert_eq!(entry.step(SimpleTime::new(23, 50, 0), 1), SimpleTime::new(0, 0, 0));
+    }
+}
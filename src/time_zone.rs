@@ -0,0 +1,171 @@
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Deserialize};
+use super::clock::Clock;
+use super::structs::*;
+use super::time_format::TimeFormat;
+
+/// What happens to a plan with a home time zone while away from it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TravelMode {
+    /// Activities keep their times in the home time zone, shown converted to the local one
+    StayAnchored,
+    /// Activities happen at the same local time wherever you are
+    FollowMe,
+}
+
+impl TravelMode {
+    pub const ALL: [TravelMode; 2] = [TravelMode::StayAnchored, TravelMode::FollowMe];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TravelMode::StayAnchored => "Stay anchored",
+            TravelMode::FollowMe => "Follow me",
+        }
+    }
+}
+
+/// When a wall time happens in `zone`, around daylight saving changes too:
+/// - times skipped when the clocks go forward happen at the end of the gap, as the clocks jump
+/// - times repeated when the clocks go back happen once, the first time round
+pub fn resolve<Z: TimeZone>(zone: &Z, time: NaiveDateTime) -> DateTime<Utc> {
+    match zone.from_local_datetime(&time) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.with_timezone(&Utc),
+        LocalResult::None => {
+            // Gaps end on a whole minute, and last at most a few hours
+            let minute = time.with_second(0).unwrap_or(time);
+            (1..=24 * 60)
+                .find_map(|minutes| zone.from_local_datetime(&(minute + Duration::minutes(minutes))).earliest())
+                .map_or_else(|| time.and_utc(), |time| time.with_timezone(&Utc))
+        }
+    }
+}
+
+/// Converts a wall time from one time zone to another
+fn convert<From: TimeZone, To: TimeZone>(time: NaiveDateTime, from: &From, to: &To) -> NaiveDateTime {
+    resolve(from, time).with_timezone(to).naive_local()
+}
+
+/// Tells the time in the plan's home time zone, from a clock telling the local time.
+/// Without a home time zone it's the local time.
+pub struct HomeClock {
+    pub inner: Box<dyn Clock>,
+    pub home: Option<Tz>,
+    pub day_start: SimpleTime,
+}
+
+impl HomeClock {
+    /// Converts plan times on the current home day to local times for display
+    pub fn local_times(&self, format: TimeFormat) -> LocalTimes {
+        LocalTimes {
+            home: self.home,
+            date: self.now().date(),
+            format,
+        }
+    }
+}
+
+#[cfg(test)]
+impl HomeClock {
+    /// A clock stopped at `now`, with the plan in `home` and its days starting at `day_start`
+    pub fn fixed(now: DateTime<Utc>, home: Tz, day_start: SimpleTime) -> Self {
+        HomeClock {
+            inner: Box::new(super::clock::FixedClock(now)),
+            home: Some(home),
+            day_start,
+        }
+    }
+}
+
+impl Clock for HomeClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.inner.now_utc()
+    }
+
+    fn now(&self) -> NaiveDateTime {
+        match self.home {
+            Some(home) => self.now_utc().with_timezone(&home).naive_local(),
+            None => self.inner.now(),
+        }
+    }
+
+    fn instant_of(&self, date: NaiveDate, time: &SimpleTime) -> DateTime<Utc> {
+        match self.home {
+            Some(home) => {
+                let time = NaiveTime::from_num_seconds_from_midnight_opt(time.as_seconds(), 0).unwrap_or_default();
+                resolve(&home, date.and_time(time))
+            }
+            None => self.inner.instant_of(date, time),
+        }
+    }
+
+    fn day_start(&self) -> SimpleTime {
+        self.day_start
+    }
+
+    fn speed(&self) -> f64 {
+        self.inner.speed()
+    }
+
+    fn is_simulated(&self) -> bool {
+        self.inner.is_simulated()
+    }
+}
+
+/// Shows plan times, which are in the home time zone, as local times
+#[derive(Clone, Copy)]
+pub struct LocalTimes {
+    home: Option<Tz>,
+    /// Home day the times are on, as the offset may change from day to day
+    date: NaiveDate,
+    format: TimeFormat,
+}
+
+impl LocalTimes {
+    pub fn datetime(&self, time: NaiveDateTime) -> NaiveDateTime {
+        match self.home {
+            Some(home) => convert(time, &home, &Local),
+            None => time,
+        }
+    }
+
+    pub fn time(&self, time: &SimpleTime) -> SimpleTime {
+        let home_time = NaiveTime::from_num_seconds_from_midnight_opt(time.as_seconds(), 0).unwrap_or_default();
+        let local_time = self.datetime(self.date.and_time(home_time));
+        SimpleTime::from_seconds(local_time.num_seconds_from_midnight())
+    }
+
+    /// The local time, formatted for display
+    pub fn show(&self, time: &SimpleTime) -> String {
+        self.format.time(&self.time(time))
+    }
+
+    pub fn format(&self) -> &TimeFormat {
+        &self.format
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Europe::Berlin;
+    use super::*;
+
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn skipped_times_happen_at_the_end_of_the_gap() {
+        // Clocks go from 02:00 to 03:00 CEST, which is 01:00 UTC
+        assert_eq!(resolve(&Berlin, at(3, 29, 2, 30)), at(3, 29, 1, 0).and_utc());
+        assert_eq!(resolve(&Berlin, at(3, 29, 3, 0)), at(3, 29, 1, 0).and_utc());
+        assert_eq!(resolve(&Berlin, at(3, 28, 2, 30)), at(3, 28, 1, 30).and_utc());
+    }
+
+    #[test]
+    fn repeated_times_happen_the_first_time_round() {
+        // 02:30 comes at 00:30 UTC in summer time, then again at 01:30 UTC
+        assert_eq!(resolve(&Berlin, at(10, 25, 2, 30)), at(10, 25, 0, 30).and_utc());
+        assert_eq!(resolve(&Berlin, at(10, 25, 3, 30)), at(10, 25, 2, 30).and_utc());
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use super::clock::Clock;
+use super::structs::*;
+
+/// Height of the rows together, in points
+const HEIGHT: f32 = 30.5 * 24.0;
+
+/// The displayed part of a plan day, from `first_hour` to `last_hour` in the plan's time zone,
+/// or the whole day from its start to the next. A whole day lasts 23 or 25 hours when the clocks
+/// change. The rows get taller or shorter so it always fills the window.
+pub struct Timeline {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl Timeline {
+    /// `visible_hours` are the first and last hours shown, in order from the day start,
+    /// with the day start as the last hour meaning the end of the day
+    pub fn new(clock: &dyn Clock, date: NaiveDate, visible_hours: Option<(u8, u8)>) -> Self {
+        let day_start = clock.day_start().hour();
+        // Hours into the plan day
+        let position = |hour: u8| (hour + 24 - day_start) % 24;
+        let (first, last) = match visible_hours {
+            Some((first, last)) => (position(first), match position(last) {
+                0 => 24,
+                last => last,
+            }),
+            None => (0, 24),
+        };
+        let (first, last) = if first < last { (first, last) } else { (0, 24) };
+
+        let at = |position: u8| {
+            let time = SimpleTime::new((day_start + position) % 24, 0, 0);
+            if position == 24 {
+                clock.plan_instant(date.succ_opt().unwrap_or(date), &time)
+            } else {
+                clock.plan_instant(date, &time)
+            }
+        };
+        Timeline {
+            start: at(first),
+            end: at(last),
+        }
+    }
+
+    pub fn hour_height(&self) -> f32 {
+        let hours = (self.end - self.start).num_seconds() as f32 / 3600.0;
+        HEIGHT / hours.max(1.0)
+    }
+
+    pub fn contains(&self, instant: DateTime<Utc>) -> bool {
+        self.start <= instant && instant <= self.end
+    }
+
+    /// Position of an instant on the timeline, in points from its top, kept within it
+    pub fn y(&self, instant: DateTime<Utc>) -> f32 {
+        let instant = instant.clamp(self.start, self.end);
+        self.hour_height() * (instant - self.start).num_seconds() as f32 / 3600.0
+    }
+
+    /// When each hour shown starts, counting a repeated hour twice and a skipped one not at all
+    pub fn hour_starts(&self) -> Vec<DateTime<Utc>> {
+        (0..)
+            .map(|hour| self.start + Duration::hours(hour))
+            .take_while(|hour_start| *hour_start < self.end)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use super::*;
+    use super::super::time_zone::HomeClock;
+
+    fn berlin_timeline(month: u32, day: u32) -> Timeline {
+        let clock = HomeClock::fixed(Utc.with_ymd_and_hms(2026, month, day, 10, 0, 0).unwrap(), chrono_tz::Europe::Berlin, SimpleTime::new(0, 0, 0));
+        Timeline::new(&clock, NaiveDate::from_ymd_opt(2026, month, day).unwrap(), None)
+    }
+
+    #[test]
+    fn days_last_as_long_as_the_clocks_say() {
+        let normal = berlin_timeline(3, 28);
+        let spring = berlin_timeline(3, 29);
+        let autumn = berlin_timeline(10, 25);
+        assert_eq!(normal.hour_starts().len(), 24);
+        assert_eq!(spring.hour_starts().len(), 23);
+        assert_eq!(autumn.hour_starts().len(), 25);
+
+        // The rows stretch or shrink to fill the same height
+        assert_eq!(normal.hour_height(), HEIGHT / 24.0);
+        assert_eq!(spring.hour_height(), HEIGHT / 23.0);
+        assert_eq!(autumn.hour_height(), HEIGHT / 25.0);
+        assert_eq!(spring.y(spring.hour_starts()[0]), 0.0);
+        assert_eq!(spring.y(spring.hour_starts()[22] + Duration::hours(1)), HEIGHT);
+    }
+}
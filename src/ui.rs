@@ -1,10 +1,35 @@
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use eframe::egui;
-use notify_rust::Notification;
 use rfd::FileDialog;
-use serde_json;
+use super::activity_form::{self, ActivityForm, QuickStarts};
+use super::catch_up;
+use super::clock::{self, Clock, SimulatedClock, SystemClock};
+use super::digest::{self, DigestKind};
 use super::color_palette::*;
+use super::history::History;
+use super::holidays::{days_off_range, Holidays};
+use super::html_export;
+use super::notifier::{ActionEvent, Notification, NotificationAction, NotificationBackend, NotificationEvent, Notifier, ToastNotifier};
+use super::org_mode;
+use super::plan_format::{self, Plan, PlanFormat};
+use super::printable::{self, Orientation, PaperSize, PrintOptions, PrintRange};
+use super::quick_add;
+use super::quiet_hours::{self, Delivery, QuietHours, QuietMode};
+use super::reminders::{self, ReminderKind};
+use super::scheduler;
+use super::settings::Settings;
+use super::sound::{SoundPlayer, SoundSource};
+use super::status::{self, Status};
 use super::structs::*;
+use super::templates::{self, PlanDay, TemplateKind};
+use super::text_plan;
+use super::time_format::{HourCycle, TimeFormat};
+use super::time_input::{self, time_picker};
+use super::time_zone::{HomeClock, TravelMode};
+use super::timeline::Timeline;
 
 pub(crate) fn load_icon() -> egui::IconData {
 	let (icon_rgba, icon_width, icon_height) = {
@@ -42,117 +67,622 @@ pub fn show_ui(app: PlannerApp) -> Result<(), eframe::Error> {
         "DailyPlanner",
         options,
         Box::new(|cc| {
-            let ctx = cc.egui_ctx.clone();
-            let ctx_clone = ctx.clone();
-
-            // Spawn a thread to send repaint requests
-            std::thread::spawn(move || loop {
-                std::thread::sleep(Duration::from_secs(1));
-                ctx_clone.request_repaint();
-            });
+            let mut app = app;
+            app.repaint_on_actions(cc.egui_ctx.clone());
             Ok(Box::new(app))
         }),
     )
 }
 
+const SNOOZE_SECONDS: i64 = 5 * 60;
+const EXTEND_SECONDS: u32 = 15 * 60;
+/// Height of the status panel between the buttons and the timeline
+const STATUS_HEIGHT: f32 = 46.0;
+/// Waking up later than this, or the clocks drifting apart by this much,
+/// means the app was asleep or the clock jumped
+const MAX_CHECK_GAP_SECONDS: i64 = 60;
+
 pub struct PlannerApp {
+    /// Tells the time in the plan's home time zone, if it keeps to one
+    clock: HomeClock,
     activities: Vec<Activity>,
-    last_update: SimpleTime,
+    /// Home time zone declared by the plan
+    time_zone: Option<Tz>,
+    settings: Settings,
+    /// Holidays and days off, from the calendars and days marked in the settings
+    holidays: Holidays,
+    /// How times are shown, following the settings and the system locale
+    time_format: TimeFormat,
+    notifier: Box<dyn Notifier>,
+    toasts: ToastNotifier,
+    sound_player: Box<dyn SoundPlayer>,
+    action_sender: Sender<ActionEvent>,
+    action_receiver: Receiver<ActionEvent>,
+    /// Notifications to show again once their snooze runs out, by the app's clock
+    snoozed: Vec<(DateTime<Utc>, Notification)>,
+    history: History,
+    /// Notifications held back during the current quiet hours, for the summary
+    suppressed: Vec<Notification>,
+    was_quiet: bool,
+    /// When the plan has to be checked again, see `scheduler::next_wakeup`
+    next_wakeup: DateTime<Utc>,
+    /// Whether the plan has to be checked on the next frame, after an action changed it
+    needs_check: bool,
+    /// When the activities were last checked, by the wall clock and by the monotonic clock
+    last_seen_utc: DateTime<Utc>,
+    last_seen_instant: Instant,
     add_activity_window_open: bool,
     close_add_activity_window: bool,
-    new_activity_name: String,
-    new_activity_start_time: (u8, u8),
-    new_activity_end_time: (u8, u8),
+    new_activity: ActivityForm,
     selected_activity_id_for_update: Option<u32>,
-    selected_activity_new_name: String,
-    selected_activity_new_start_time: (u8, u8),
-    selected_activity_new_end_time: (u8, u8),
+    selected_activity_form: ActivityForm,
     activity_to_delete_id: Option<u32>,
     update_activity_window_open: bool,
     close_update_activity_window: bool,
+    print_window_open: bool,
+    print_options: PrintOptions,
+    settings_window_open: bool,
+    paste_plan_window_open: bool,
+    paste_plan_text: String,
+    paste_plan_replace: bool,
+    quick_add_window_open: bool,
+    quick_add_text: String,
+    /// Why the saved plan couldn't be read. Saving over it waits for a confirmation,
+    /// so a typo in a hand-edited file doesn't cost the whole plan.
+    unreadable_plan: Option<String>,
+    replace_plan_window_open: bool,
+    /// Why the saved settings couldn't be read, kept until they're saved over on purpose
+    unreadable_settings: Option<String>,
+    /// Why the saved history couldn't be read, shown once it's been set aside
+    unreadable_history: Option<String>,
+    /// Category whose notification text is edited in the settings, empty for all activities
+    template_category: String,
+    /// First and last date typed for new days off, the last one left empty for a single day
+    days_off_from: String,
+    days_off_to: String,
+    /// Start and speed picked for the time simulation
+    simulation_start: SimpleTime,
+    simulation_speed: u32,
+    /// Title last given to the window, to change it only when the status does
+    window_title: String,
 }
 
 impl PlannerApp {
-    pub fn new() -> std::io::Result<Self> {
+    pub fn new(clock: Box<dyn Clock>) -> std::io::Result<Self> {
 
-        // Load activities from file
-        let mut activities: Vec<Activity> = if let Ok(activities_json) = std::fs::read_to_string("plan.json") {
-            if let Ok(activities) = serde_json::from_str(&activities_json) {
-                activities
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
+        let (settings, unreadable_settings) = match Settings::load() {
+            Ok(settings) => (settings, None),
+            Err(error) => (Settings::default(), Some(format!("{}: {}", Settings::path(), error))),
+        };
+
+        // Load activities from the saved plan, preferring the configured format
+        let plan_path = std::iter::once(settings.plan_format)
+            .chain(PlanFormat::ALL)
+            .map(|format| format.plan_path())
+            .find(|path| path.exists());
+        let (plan, unreadable_plan) = match plan_path.map(|path| (plan_format::load_plan(&path), path)) {
+            Some((Ok(plan), _)) => (plan, None),
+            Some((Err(error), path)) => (Plan::default(), Some(format!("{}: {}", path.display(), error))),
+            None => (Plan::default(), None),
         };
+        let Plan { time_zone, mut activities } = plan;
 
+        let clock = HomeClock {
+            inner: clock,
+            home: home_zone(time_zone, settings.travel_mode),
+            day_start: SimpleTime::new(settings.day_start_hour, 0, 0),
+        };
+        let holidays = Holidays::load(&settings.holiday_calendars, &settings.days_off);
+        let (mut history, unreadable_history) = load_history();
+        history.prune(clock.today());
         for activity in &mut activities {
-            activity.update_is_now();
+            activity.update_is_now(&clock, &holidays, &history);
         }
 
-        Ok(Self {
+        let toasts = ToastNotifier::default();
+        let (action_sender, action_receiver) = mpsc::channel();
+        let notifier = settings.notification_backend.notifier(&toasts, &action_sender);
+        let sound_player = settings.sounds.player();
+
+        // Catch up since the app was last running, or since the day started on the first run
+        let now_utc = clock.now_utc();
+        let lookback_limit = now_utc - chrono::Duration::hours(catch_up::MAX_LOOKBACK_HOURS);
+        let last_seen = history
+            .last_seen()
+            .unwrap_or_else(|| clock.plan_instant(clock.today(), &clock.day_start()))
+            .max(lookback_limit);
+
+        let mut app = Self {
+            clock,
             activities,
-            last_update: SimpleTime::from_now(),
+            time_zone,
+            holidays,
+            time_format: TimeFormat::new(settings.hour_cycle),
+            settings,
+            notifier,
+            toasts,
+            sound_player,
+            action_sender,
+            action_receiver,
+            snoozed: Vec::new(),
+            history,
+            suppressed: Vec::new(),
+            was_quiet: false,
+            next_wakeup: now_utc,
+            needs_check: true,
+            last_seen_utc: now_utc,
+            last_seen_instant: Instant::now(),
             add_activity_window_open: false,
             close_add_activity_window: false,
-            new_activity_name: "".to_string(),
-            new_activity_start_time: (0, 0),
-            new_activity_end_time: (0, 0),
+            new_activity: ActivityForm::default(),
             selected_activity_id_for_update: None,
-            selected_activity_new_name: "".to_string(),
-            selected_activity_new_start_time: (0, 0),
-            selected_activity_new_end_time: (0, 0),
+            selected_activity_form: ActivityForm::default(),
             activity_to_delete_id: None,
             update_activity_window_open: false,
             close_update_activity_window: false,
-        })
+            print_window_open: false,
+            print_options: PrintOptions::default(),
+            settings_window_open: false,
+            paste_plan_window_open: false,
+            paste_plan_text: "".to_string(),
+            paste_plan_replace: false,
+            quick_add_window_open: false,
+            quick_add_text: "".to_string(),
+            unreadable_plan,
+            replace_plan_window_open: false,
+            unreadable_settings,
+            unreadable_history,
+            days_off_from: "".to_string(),
+            days_off_to: "".to_string(),
+            template_category: "".to_string(),
+            simulation_start: SimpleTime::new(6, 0, 0),
+            simulation_speed: 600,
+            window_title: "DailyPlanner".to_string(),
+        };
+        if let Some(error) = app.unreadable_plan.clone() {
+            app.show_error("Couldn't read the plan", &error);
+        }
+        if let Some(error) = app.unreadable_settings.clone() {
+            app.show_error("Couldn't read the settings", &error);
+        }
+        if let Some(error) = app.unreadable_history.take() {
+            app.show_error("Couldn't read the history", &error);
+        }
+        app.catch_up(last_seen, now_utc);
+        app.mark_seen();
+        Ok(app)
     }
 
-    fn update_activities_every_ten_seconds(&mut self) {
-        // Update the activities after 10 seconds from last update
-        let now = SimpleTime::from_now();
-        if now.as_seconds() - self.last_update.as_seconds() >= 10 {
-            self.last_update = now;
-            self.update_activities_with_notifications();
+    /// Wakes the UI up when an action is picked on a desktop notification,
+    /// which arrives on another thread while the app may be asleep
+    pub fn repaint_on_actions(&mut self, ctx: egui::Context) {
+        let (sender, receiver) = mpsc::channel();
+        let actions = std::mem::replace(&mut self.action_receiver, receiver);
+        std::thread::spawn(move || {
+            for event in actions {
+                if sender.send(event).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Catches up if the app woke up late, or the clock jumped since the last check
+    fn catch_up_after_gap(&mut self) {
+        // In real seconds, as a simulated clock may run faster
+        let speed = self.clock.speed();
+        let now_utc = self.clock.now_utc();
+        let lateness = (now_utc - self.next_wakeup).num_seconds() as f64 / speed;
+        let wall_elapsed = (now_utc - self.last_seen_utc).num_seconds() as f64 / speed;
+        // The monotonic clock ignores clock changes
+        let monotonic_elapsed = self.last_seen_instant.elapsed().as_secs_f64();
+        let max_gap = MAX_CHECK_GAP_SECONDS as f64;
+        if lateness > max_gap || (wall_elapsed - monotonic_elapsed).abs() > max_gap {
+            let lookback_limit = now_utc - chrono::Duration::hours(catch_up::MAX_LOOKBACK_HOURS);
+            self.catch_up(self.last_seen_utc.max(lookback_limit), now_utc);
         }
+        self.mark_seen();
     }
 
-    fn update_activities_with_notifications(&mut self) {
-        // Update the activities and show notifications if needed
-        for activity in &mut self.activities {
-            if activity.update_is_now() {
-                if activity.is_now {
-                    Notification::new()
-                        .appname("DailyPlanner")
-                        .summary("Activity Started")
-                        .body(&format!("{} has started.", activity.name))
-                        .icon("icon")
-                        .show()
-                        .expect("Failed to show notification");
+    /// Works out when the plan or a snoozed notification needs checking next
+    fn schedule_wakeup(&mut self) {
+        let next_wakeup = scheduler::next_wakeup(&self.activities, &self.settings, &self.clock, self.clock.inner.as_ref(), &self.history);
+        self.next_wakeup = self.snoozed.iter().map(|(until, _)| *until).fold(next_wakeup, DateTime::min);
+    }
+
+    /// Asks for the next repaint once the next wakeup comes, the status moves on or a toast expires
+    fn request_wakeup_repaint(&self, ctx: &egui::Context) {
+        let now_utc = self.clock.now_utc();
+        let delay = scheduler::real_delay(
+            self.next_wakeup.min(scheduler::next_minute(now_utc)),
+            now_utc,
+            self.clock.speed(),
+            self.toasts.next_expiry(),
+        );
+        ctx.request_repaint_after(delay);
+    }
+
+    /// Shows a problem with the files in a toast, which stays clear of quiet hours
+    fn show_error(&mut self, summary: &str, error: &str) {
+        eprintln!("{}: {}", summary, error);
+        if let Err(error) = self.toasts.notify(&Notification::new(summary, error), self.clock.inner.now()) {
+            eprintln!("Failed to show notification: {}", error);
+        }
+    }
+
+    fn save_plan(&mut self) {
+        let path = self.settings.plan_format.plan_path();
+        match plan_format::save_plan(&path, &self.activities, self.time_zone, self.settings.pretty_json) {
+            Ok(()) => self.unreadable_plan = None,
+            Err(error) => self.show_error("Couldn't save the plan", &format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    fn save_settings(&mut self) {
+        match self.settings.save() {
+            Ok(()) => self.unreadable_settings = None,
+            Err(error) => self.show_error("Couldn't save the settings", &format!("{}: {}", Settings::path(), error)),
+        }
+    }
+
+    /// Saves the history, unless the time is simulated
+    fn save_history(&mut self) {
+        if self.clock.is_simulated() {
+            return;
+        }
+        self.history.prune(self.clock.today());
+        if let Err(error) = self.history.save() {
+            eprintln!("Failed to save history: {}", error);
+        }
+    }
+
+    /// Switches to another clock, quietly bringing the activities to its time
+    fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        if self.clock.is_simulated() {
+            // Forget what happened on the simulated day
+            let (history, unreadable_history) = load_history();
+            self.history = history;
+            if let Some(error) = unreadable_history {
+                self.show_error("Couldn't read the history", &error);
+            }
+        }
+        self.clock.inner = clock;
+        self.restart_clock();
+    }
+
+    /// Follows a change of the plan's time zone or of the travel mode
+    fn update_home_zone(&mut self) {
+        let home = home_zone(self.time_zone, self.settings.travel_mode);
+        if home != self.clock.home {
+            self.clock.home = home;
+            self.restart_clock();
+        }
+    }
+
+    /// Starts over after the time jumped on purpose, without notifying what happened in between
+    fn restart_clock(&mut self) {
+        self.suppressed.clear();
+        self.snoozed.clear();
+        self.update_activities();
+        self.was_quiet = quiet_hours::is_quiet(&self.settings.quiet_hours, &self.local_now());
+        self.next_wakeup = self.clock.now_utc();
+        self.mark_seen();
+    }
+
+    /// The local time of day, for personal settings like quiet hours and digests,
+    /// which don't follow the plan's home time zone
+    fn local_now(&self) -> SimpleTime {
+        SimpleTime::from_clock(self.clock.inner.as_ref())
+    }
+
+    fn mark_seen(&mut self) {
+        self.last_seen_utc = self.clock.now_utc();
+        self.last_seen_instant = Instant::now();
+        self.history.set_last_seen(self.last_seen_utc);
+        self.save_history();
+    }
+
+    /// Brings the activities up to date and announces every start and end
+    /// between `from` and `to` in one notification, instead of one each
+    fn catch_up(&mut self, from: DateTime<Utc>, to: DateTime<Utc>) {
+        self.update_activities();
+
+        let today = self.clock.inner.now().date();
+        let lines: Vec<String> = catch_up::missed_transitions(&self.activities, from, to, &self.clock, &self.holidays, &self.history)
+            .iter()
+            .filter_map(|transition| {
+                let activity = self.activities
+                    .iter()
+                    .find(|activity| activity.id == transition.activity_id && !activity.silent)?;
+                let verb = match transition.event {
+                    NotificationEvent::Start => "started",
+                    _ => "ended",
+                };
+                let at = transition.at.with_timezone(&chrono::Local).naive_local();
+                // Times before today are shown with their date
+                let when = if at.date() == today {
+                    self.time_format.wall_time(&at)
                 } else {
-                    Notification::new()
-                        .appname("DailyPlanner")
-                        .summary("Activity Ended")
-                        .body(&format!("{} has ended.", activity.name))
-                        .icon("icon")
-                        .show()
-                        .expect("Failed to show notification");
+                    format!("{} {}", self.time_format.date(at.date()), self.time_format.wall_time(&at))
+                };
+                Some(format!("{} {} {}", when, activity.name, verb))
+            })
+            .collect();
+        if !lines.is_empty() {
+            self.notify(Notification::new("While You Were Away", &lines.join("\n")));
+        }
+    }
+
+    fn notify(&mut self, notification: Notification) {
+        let sent_at = self.clock.inner.now();
+        let is_silent = notification.activity_id
+            .and_then(|id| self.activities.iter().find(|activity| activity.id == id))
+            .is_some_and(|activity| activity.silent);
+        let is_quiet = quiet_hours::is_quiet(&self.settings.quiet_hours, &self.local_now());
+        match quiet_hours::delivery(is_silent, is_quiet, self.settings.quiet_mode, self.settings.summarize_quiet_hours) {
+            Delivery::Show => {}
+            Delivery::InApp => {
+                if let Err(error) = self.toasts.notify(&notification, sent_at) {
+                    eprintln!("Failed to show notification: {}", error);
                 }
+                return;
+            }
+            Delivery::Hold => {
+                self.suppressed.push(notification);
+                return;
+            }
+            Delivery::Drop => return,
+        }
+
+        if let Err(error) = self.notifier.notify(&notification, sent_at) {
+            eprintln!("Failed to show notification: {}", error);
+        }
+
+        if let Some(event) = notification.event {
+            let category = notification.activity_id
+                .and_then(|id| self.activities.iter().find(|activity| activity.id == id))
+                .map_or("", |activity| activity.category());
+            let source = self.settings.sounds.source(event, category);
+            self.play_sound(&source);
+        }
+    }
+
+    fn play_sound(&mut self, source: &SoundSource) {
+        if let Err(error) = self.sound_player.play(source, self.settings.sounds.volume) {
+            eprintln!("Failed to play sound: {}", error);
+        }
+    }
+
+    fn handle_action(&mut self, event: ActionEvent) {
+        self.needs_check = true;
+        let (Some(activity_id), Some(date)) = (event.notification.activity_id, event.notification.date) else {
+            return;
+        };
+        match event.action {
+            NotificationAction::Done => {
+                self.history.set_done(date, activity_id);
+                self.save_history();
+            }
+            NotificationAction::Acknowledge => {
+                self.history.set_acknowledged(date, activity_id);
+                self.save_history();
+            }
+            NotificationAction::Snooze => {
+                self.snoozed.push((self.clock.now_utc() + chrono::Duration::seconds(SNOOZE_SECONDS), event.notification));
+            }
+            NotificationAction::Extend => {
+                // Only the run notified of is extended, the plan stays as it is
+                self.history.extend(date, activity_id, EXTEND_SECONDS);
+                self.save_history();
+                if let Some(activity) = self.activities.iter_mut().find(|activity| activity.id == activity_id) {
+                    // Resuming an activity that already ended shouldn't announce it again
+                    activity.update_is_now(&self.clock, &self.holidays, &self.history);
+                }
+            }
+        }
+    }
+
+    fn process_notification_actions(&mut self) {
+        while let Ok(event) = self.action_receiver.try_recv() {
+            self.handle_action(event);
+        }
+
+        let now = self.clock.now_utc();
+        let (due, pending): (Vec<_>, Vec<_>) = self.snoozed.drain(..).partition(|(until, _)| *until <= now);
+        self.snoozed = pending;
+        for (_, notification) in due {
+            self.notify(notification);
+        }
+    }
+
+    /// Sends the summary of what was suppressed once quiet hours are over,
+    /// returning true if it did
+    fn update_quiet_hours(&mut self) -> bool {
+        let is_quiet = quiet_hours::is_quiet(&self.settings.quiet_hours, &self.local_now());
+        let summary = quiet_hours::summary(&mut self.suppressed, self.was_quiet, is_quiet);
+        self.was_quiet = is_quiet;
+        let summarized = summary.is_some();
+        if let Some(summary) = summary {
+            self.notify(summary);
+        }
+        summarized
+    }
+
+    /// Returns true if an activity started or ended, or a notification was sent
+    fn update_activities_with_notifications(&mut self) -> bool {
+        let summarized = self.update_quiet_hours();
+        let local_times = self.clock.local_times(self.time_format);
+
+        // Update the activities and show notifications if needed
+        let changed_ids: Vec<u32> = self.activities
+            .iter_mut()
+            .filter_map(|activity| activity.update_is_now(&self.clock, &self.holidays, &self.history).then_some(activity.id))
+            .collect();
+        let plan_day = PlanDay {
+            date: self.clock.today(),
+            day_start: self.clock.day_start(),
+            holidays: &self.holidays,
+        };
+        let mut notifications = Vec::new();
+        let now_utc = self.clock.now_utc();
+        let yesterday = plan_day.date.pred_opt().unwrap_or(plan_day.date);
+        for activity in self.activities.iter().filter(|activity| changed_ids.contains(&activity.id)) {
+            let kind = if activity.is_now { TemplateKind::Start } else { TemplateKind::End };
+            // An activity ending as the next plan day begins ran on the day before
+            let date = if activity.is_now
+                || activity.span(&self.clock, plan_day.date, &self.history).is_some_and(|(start, _)| start <= now_utc)
+            {
+                plan_day.date
+            } else {
+                yesterday
+            };
+            let mut actions = vec![NotificationAction::Done, NotificationAction::Snooze, NotificationAction::Extend];
+            if activity.is_now && activity.insistent.is_some() {
+                actions.push(NotificationAction::Acknowledge);
+            }
+            notifications.push(
+                self.settings.templates
+                    .notification(kind, activity, &self.activities, &PlanDay { date, ..plan_day }, 0, &local_times)
+                    .with_actions(&actions),
+            );
+        }
+
+        // Advance reminders, once per activity, kind and time of the event, even across restarts.
+        // An extended activity gets another reminder before its new end.
+        let today = self.clock.today();
+        let mut reminders_sent = false;
+        for reminder in reminders::due_reminders(&self.activities, &self.settings, &self.clock, &self.holidays, &self.history) {
+            if self.history.reminder_sent(reminder.date, reminder.activity_id, reminder.kind, reminder.event) {
+                continue;
+            }
+            self.history.set_reminder_sent(reminder.date, reminder.activity_id, reminder.kind, reminder.event);
+            reminders_sent = true;
+            let Some(activity) = self.activities.iter().find(|activity| activity.id == reminder.activity_id) else {
+                continue;
+            };
+            let actions = match reminder.kind {
+                ReminderKind::BeforeStart => &[NotificationAction::Snooze][..],
+                ReminderKind::BeforeEnd => &[NotificationAction::Snooze, NotificationAction::Extend][..],
+            };
+            notifications.push(
+                self.settings.templates
+                    .notification(
+                        TemplateKind::reminder(reminder.kind),
+                        activity,
+                        &self.activities,
+                        &PlanDay { date: reminder.date, ..plan_day },
+                        reminder.minutes_left,
+                        &local_times,
+                    )
+                    .with_actions(actions),
+            );
+        }
+
+        // Repeat the start of insistent activities until they're acknowledged or done.
+        // Repeats missed while the app was closed are sent as one.
+        let mut repeats_sent = Vec::new();
+        for activity in &self.activities {
+            let due = reminders::due_repeats(activity, &self.clock, &self.history);
+            if !activity.occurs_on(today, &self.holidays)
+                || due <= self.history.repeats_sent(today, activity.id)
+                || self.history.is_acknowledged(today, activity.id)
+                || self.history.is_done(today, activity.id)
+            {
+                continue;
             }
+            let minutes_since_start = reminders::minutes_since_start(activity, &self.clock, &self.history);
+            notifications.push(
+                self.settings.templates
+                    .notification(TemplateKind::Repeat, activity, &self.activities, &plan_day, minutes_since_start, &local_times)
+                    .with_actions(&[NotificationAction::Acknowledge, NotificationAction::Done]),
+            );
+            repeats_sent.push((activity.id, due));
+        }
+        for (activity_id, count) in &repeats_sent {
+            self.history.set_repeats_sent(today, *activity_id, *count);
+        }
+        if reminders_sent || !repeats_sent.is_empty() {
+            self.save_history();
         }
+
+        // Daily digests, once a day each
+        for kind in DigestKind::ALL {
+            let Some(time) = self.settings.digest_time(kind) else {
+                continue;
+            };
+            let Some(since_due) = digest::seconds_since_due(&time, &self.local_now()) else {
+                continue;
+            };
+            // A digest sent late, maybe after midnight, is about the plan day it was due on
+            let date = self.clock.plan_date(self.clock.now() - chrono::Duration::seconds(since_due as i64));
+            if self.history.digest_sent(kind, date) {
+                continue;
+            }
+            notifications.push(match kind {
+                DigestKind::StartOfDay => digest::start_of_day(&self.activities, &self.clock, date, &self.holidays, &local_times),
+                DigestKind::EndOfDay => digest::end_of_day(&self.activities, &self.history, &self.clock, date, &self.holidays, &local_times),
+            });
+            self.history.set_digest_sent(kind, date);
+            self.save_history();
+        }
+
+        let changed = summarized || !changed_ids.is_empty() || !notifications.is_empty();
+        for notification in notifications {
+            self.notify(notification);
+        }
+        changed
+    }
+
+    /// Categories used in the plan, sorted and without duplicates
+    fn categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = self.activities
+            .iter()
+            .map(|activity| activity.category().to_string())
+            .filter(|category| !category.is_empty())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+
+    /// Start times offered in the activity windows, leaving out the activity being edited
+    fn quick_starts(&self, editing_id: Option<u32>) -> QuickStarts {
+        let now = SimpleTime::from_clock(&self.clock);
+        // Times are compared from the day start, so an activity after midnight comes after the evening
+        let day_start = self.clock.day_start();
+        let after_previous = self.activities
+            .iter()
+            .filter(|activity| Some(activity.id) != editing_id)
+            .filter(|activity| activity.start_time().seconds_into_day(&day_start) <= now.seconds_into_day(&day_start))
+            .max_by_key(|activity| activity.end_time().seconds_into_day(&day_start))
+            .map(|activity| *activity.end_time());
+        QuickStarts {
+            now: SimpleTime::new(now.hour(), now.minute(), 0),
+            after_previous,
+        }
+    }
+
+    fn next_activity_id(&self) -> u32 {
+        self.activities.iter().map(|activity| activity.id + 1).max().unwrap_or(0)
     }
 
     fn update_activities(&mut self) {
         // Update the activities
         for activity in &mut self.activities {
-            activity.update_is_now();
+            activity.update_is_now(&self.clock, &self.holidays, &self.history);
         }
     }
 }
 
 impl eframe::App for PlannerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.update_activities_every_ten_seconds();
+        self.process_notification_actions();
+        // Only scheduled wakeups count as seen, to notice when one comes late
+        let is_due = self.clock.now_utc() >= self.next_wakeup;
+        if is_due {
+            self.catch_up_after_gap();
+        }
 
         let visuals = egui::Visuals {
             panel_fill: DARK_GREY,
@@ -176,35 +706,127 @@ impl eframe::App for PlannerApp {
                     self.activities = vec![];
                 }
                 if ui.button("Save Plan").clicked() {
-                    let activities_json = serde_json::to_string(&self.activities).unwrap();
-                    std::fs::write("plan.json", activities_json).expect("Failed to save plan");
+                    if self.unreadable_plan.is_some() {
+                        self.replace_plan_window_open = true;
+                    } else {
+                        self.save_plan();
+                    }
                 }
             });
 
+            if self.replace_plan_window_open {
+                let mut replace = false;
+                egui::Window::new("Replace Plan")
+                    .title_bar(false)
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ui.ctx(), |ui| {
+                        if let Some(error) = &self.unreadable_plan {
+                            ui.label(egui::RichText::new(format!("The saved plan couldn't be read:\n{}", error)).color(RED));
+                        }
+                        ui.label("Saving replaces it with the activities shown here.");
+                        ui.horizontal(|ui| {
+                            replace = ui.button("Replace").clicked();
+                            if ui.button("Cancel").clicked() {
+                                self.replace_plan_window_open = false;
+                            }
+                        });
+                    });
+                if replace {
+                    self.replace_plan_window_open = false;
+                    self.save_plan();
+                }
+            }
+
             ui.horizontal(|ui| {
-                if ui.button("Export Plan").clicked() {
-                    if let Some(path) = FileDialog::new()
-                        .add_filter("JSON", &["json"])
-                        .set_file_name("plan.json")
+                if ui.button("Export").clicked() {
+                    let mut dialog = FileDialog::new();
+                    for format in PlanFormat::ALL {
+                        dialog = dialog.add_filter(format.label(), format.extensions());
+                    }
+                    if let Some(path) = dialog
+                        .add_filter("HTML", &["html", "htm"])
+                        .add_filter("Org", &["org"])
+                        .set_file_name(self.settings.plan_format.plan_path().to_string_lossy())
                         .save_file()
                     {
-                        let activities_json = serde_json::to_string(&self.activities).unwrap();
-                        std::fs::write(path, activities_json).expect("Failed to save plan");
+                        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+                        let result = match extension.as_str() {
+                            "html" | "htm" => std::fs::write(&path, html_export::to_html(&self.activities, &self.time_format, self.clock.home, &self.clock.day_start(), self.clock.today(), &self.holidays)),
+                            "org" => std::fs::write(&path, org_mode::to_org(&self.activities, self.clock.today())),
+                            _ => plan_format::save_plan(&path, &self.activities, self.time_zone, self.settings.pretty_json),
+                        };
+                        if let Err(error) = result {
+                            self.show_error("Couldn't export the plan", &format!("{}: {}", path.display(), error));
+                        }
                     }
                 }
-                if ui.button("Import Plan").clicked() {
-                    if let Some(path) = FileDialog::new()
-                        .add_filter("JSON", &["json"])
+                if ui.button("Import").clicked() {
+                    let mut all_extensions: Vec<&str> = PlanFormat::ALL
+                        .iter()
+                        .flat_map(|format| format.extensions().iter().copied())
+                        .collect();
+                    all_extensions.extend(["org", "txt"]);
+                    let mut dialog = FileDialog::new().add_filter("Plan", &all_extensions);
+                    for format in PlanFormat::ALL {
+                        dialog = dialog.add_filter(format.label(), format.extensions());
+                    }
+                    if let Some(path) = dialog
+                        .add_filter("Org", &["org"])
+                        .add_filter("Text", &["txt"])
                         .pick_file()
                     {
-                        if let Ok(activities_json) = std::fs::read_to_string(path) {
-                            if let Ok(activities) = serde_json::from_str(&activities_json) {
-                                self.activities = activities;
+                        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+                        if extension == "txt" {
+                            // Text plans go through the paste dialog so unparsed lines can be reviewed
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => {
+                                    self.paste_plan_text = text;
+                                    self.paste_plan_replace = true;
+                                    self.paste_plan_window_open = true;
+                                }
+                                Err(error) => self.show_error("Couldn't import the plan", &format!("{}: {}", path.display(), error)),
                             }
+                        } else {
+                            // Org files don't have a time zone, so the current one is kept
+                            let time_zone = self.time_zone;
+                            let mut skipped = Vec::new();
+                            let plan = match extension.as_str() {
+                                "org" => std::fs::read_to_string(&path).map(|org| {
+                                    let (activities, skipped_headings) = org_mode::from_org(&org);
+                                    skipped = skipped_headings;
+                                    Plan { time_zone, activities }
+                                }),
+                                _ => plan_format::load_plan(&path),
+                            };
+                            match plan {
+                                Ok(plan) => {
+                                    self.activities = plan.activities;
+                                    self.time_zone = plan.time_zone;
+                                    if !skipped.is_empty() {
+                                        let lines: Vec<String> = skipped
+                                            .iter()
+                                            .map(|skipped| format!("{}: {}", skipped.heading, skipped.reason))
+                                            .collect();
+                                        self.show_error("Some headings weren't imported", &lines.join("\n"));
+                                    }
+                                }
+                                Err(error) => self.show_error("Couldn't import the plan", &format!("{}: {}", path.display(), error)),
+                            }
+                            self.update_activities();
+                            self.update_home_zone();
                         }
-                        self.update_activities();
                     }
                 }
+                if ui.button("Print").clicked() {
+                    self.print_window_open = true;
+                }
+                if ui.button("⚡").on_hover_text("Quick Add").clicked() {
+                    self.quick_add_window_open = true;
+                }
+                if ui.button("⚙").clicked() {
+                    self.settings_window_open = true;
+                }
             });
 
             if let Some(activity_id) = self.activity_to_delete_id {
@@ -218,6 +840,9 @@ impl eframe::App for PlannerApp {
             }
 
             if self.add_activity_window_open {
+                let new_activity_id = self.next_activity_id();
+                let quick_starts = self.quick_starts(None);
+                let day_start = self.clock.day_start();
                 egui::Window::new("New Activity")
                     .default_size(egui::vec2(140.0, 70.0))
                     .title_bar(false)
@@ -225,33 +850,30 @@ impl eframe::App for PlannerApp {
                     .resizable(false)
                     .open(&mut self.add_activity_window_open)
                     .show(ui.ctx(), |ui| {
-                        ui.label("Name:");
-                        ui.text_edit_singleline(&mut self.new_activity_name);
-
-                        ui.label("Start Time:");
-                        time_picker(ui, &mut self.new_activity_start_time, "new_activity_start_time");
-
-                        ui.label("End Time:");
-                        time_picker(ui, &mut self.new_activity_end_time, "new_activity_end_time");
+                        if let Some(home) = self.clock.home {
+                            ui.label(format!("Times in {}", home.name()));
+                        }
+                        self.new_activity.show(ui, "new_activity", &self.time_format, &self.settings.time_entry, &quick_starts, &day_start);
                         
                         ui.add_space(5.0);
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
-                            if ui.button("Add").clicked() {
-                                self.activities.push(Activity::new(
-                                    self.activities.len() as u32,
-                                    self.new_activity_name.to_ascii_uppercase(),
-                                    SimpleTime::new(self.new_activity_start_time.0, self.new_activity_start_time.1, 0),
-                                    SimpleTime::new(self.new_activity_end_time.0, self.new_activity_end_time.1, 0),
-                                ));
-                                self.new_activity_name = "".to_string();
-                                self.new_activity_end_time = (0, 0);
-                                self.new_activity_start_time = (0, 0);
+                            if ui.add_enabled(!self.new_activity.crosses_day_end(&day_start), egui::Button::new("Add")).clicked() {
+                                self.activities.push(self.new_activity.to_activity(new_activity_id));
+                                self.new_activity = ActivityForm {
+                                    timing: self.new_activity.timing,
+                                    ..ActivityForm::default()
+                                };
                                 self.close_add_activity_window = true;
                             }
                             if ui.button("Cancel").clicked() {
                                 self.close_add_activity_window = true;
                             }
+                            if ui.button("Paste Plan").clicked() {
+                                self.paste_plan_replace = false;
+                                self.paste_plan_window_open = true;
+                                self.close_add_activity_window = true;
+                            }
                         });
 
                     });
@@ -264,6 +886,8 @@ impl eframe::App for PlannerApp {
 
             if self.update_activity_window_open {
                 if let Some(activity_id) = self.selected_activity_id_for_update {
+                    let quick_starts = self.quick_starts(Some(activity_id));
+                    let day_start = self.clock.day_start();
                     if let Some(activity) = self.activities.iter_mut().find(|p| p.id == activity_id) {
                         egui::Window::new("Update Activity")
                             .default_size(egui::vec2(140.0, 70.0))
@@ -272,22 +896,16 @@ impl eframe::App for PlannerApp {
                             .resizable(false)
                             .open(&mut self.update_activity_window_open)
                             .show(ui.ctx(), |ui| {
-                                ui.label("Name:");
-                                ui.text_edit_singleline(&mut self.selected_activity_new_name);
-
-                                ui.label("Start Time:");
-                                time_picker(ui, &mut self.selected_activity_new_start_time, "update_activity_start_time");
-
-                                ui.label("End Time:");
-                                time_picker(ui, &mut self.selected_activity_new_end_time, "update_activity_end_time");
+                                if let Some(home) = self.clock.home {
+                                    ui.label(format!("Times in {}", home.name()));
+                                }
+                                self.selected_activity_form.show(ui, "update_activity", &self.time_format, &self.settings.time_entry, &quick_starts, &day_start);
                                 
                                 ui.add_space(5.0);
 
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
-                                    if ui.button("Update").clicked() {
-                                        activity.name = self.selected_activity_new_name.to_ascii_uppercase();
-                                        activity.start_time = SimpleTime::new(self.selected_activity_new_start_time.0, self.selected_activity_new_start_time.1, 0);
-                                        activity.end_time = SimpleTime::new(self.selected_activity_new_end_time.0, self.selected_activity_new_end_time.1, 0);
+                                    if ui.add_enabled(!self.selected_activity_form.crosses_day_end(&day_start), egui::Button::new("Update")).clicked() {
+                                        self.selected_activity_form.apply_to(activity);
                                         self.close_update_activity_window = true;
                                     }
                                     if ui.button("Cancel").clicked() {
@@ -304,115 +922,855 @@ impl eframe::App for PlannerApp {
                 }
             }
 
-            // First, draw the hours as rows of rectangles
-            ui.vertical(|ui| {
-                for h in 0..=23 {
-                    ui.allocate_ui_with_layout(
-                        egui::vec2(100.0, 40.0),
-                        egui::Layout::left_to_right(egui::Align::Min),
-                        |ui| {
-                            if SimpleTime::from_now().hour() == h {
-                                ui.label(
-                                    egui::RichText::new(format!("{:02}:00", h))
-                                        .size(20.0)
-                                        .color(WHITE),
-                                );
-                            } else {
-                                ui.label(
-                                    egui::RichText::new(format!("{:02}:00", h))
-                                        .size(20.0)
-                                        .color(GREY),
-                                );
-                            }
-
-                        },
-                    );
+            if self.print_window_open {
+                let mut print_window_open = true;
+                egui::Window::new("Print Plan")
+                    .default_size(egui::vec2(140.0, 70.0))
+                    .title_bar(false)
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut print_window_open)
+                    .show(ui.ctx(), |ui| {
+                        let options = &mut self.print_options;
+
+                        ui.label("Range:");
+                        ui.horizontal(|ui| {
+                            for range in [PrintRange::Day, PrintRange::Week] {
+                                ui.radio_value(&mut options.range, range, range.label());
+                            }
+                        });
+
+                        ui.label("Paper:");
+                        ui.horizontal(|ui| {
+                            for paper_size in [PaperSize::A4, PaperSize::Letter] {
+                                ui.radio_value(&mut options.paper_size, paper_size, paper_size.label());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            for orientation in [Orientation::Portrait, Orientation::Landscape] {
+                                ui.radio_value(&mut options.orientation, orientation, orientation.label());
+                            }
+                        });
+
+                        ui.label("Hours:");
+                        hour_range_picker(ui, &mut options.first_hour, &mut options.last_hour, self.settings.day_start_hour, "print_hours", &self.time_format);
+
+                        ui.add_space(5.0);
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            if ui.button("PDF").clicked() {
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("PDF", &["pdf"])
+                                    .set_file_name("plan.pdf")
+                                    .save_file()
+                                {
+                                    let pdf = printable::to_pdf(&self.activities, &self.print_options, &self.time_format, self.clock.today(), &self.clock.day_start(), &self.holidays);
+                                    match std::fs::write(&path, pdf) {
+                                        Ok(()) => self.print_window_open = false,
+                                        Err(error) => self.show_error("Couldn't print the plan", &format!("{}: {}", path.display(), error)),
+                                    }
+                                }
+                            }
+                            if ui.button("SVG").clicked() {
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("SVG", &["svg"])
+                                    .set_file_name("plan.svg")
+                                    .save_file()
+                                {
+                                    let svg = printable::to_svg(&self.activities, &self.print_options, &self.time_format, self.clock.today(), &self.clock.day_start(), &self.holidays);
+                                    match std::fs::write(&path, svg) {
+                                        Ok(()) => self.print_window_open = false,
+                                        Err(error) => self.show_error("Couldn't print the plan", &format!("{}: {}", path.display(), error)),
+                                    }
+                                }
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.print_window_open = false;
+                            }
+                        });
+                    });
+                if !print_window_open {
+                    self.print_window_open = false;
                 }
+            }
+
+            if self.settings_window_open {
+                let mut settings_window_open = true;
+                egui::Window::new("Settings")
+                    .default_size(egui::vec2(140.0, 70.0))
+                    .title_bar(false)
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut settings_window_open)
+                    .show(ui.ctx(), |ui| {
+                        egui::ScrollArea::vertical().max_height(600.0).show(ui, |ui| {
+                            ui.label("Plan file format:");
+                            ui.horizontal(|ui| {
+                                for format in PlanFormat::ALL {
+                                    ui.radio_value(&mut self.settings.plan_format, format, format.label());
+                                }
+                            });
+                            ui.checkbox(&mut self.settings.pretty_json, "Pretty-print JSON");
+
+                            ui.label("Plan time zone:");
+                            egui::ComboBox::from_id_salt("plan_time_zone")
+                                .width(200.0)
+                                .selected_text(self.time_zone.map_or("Local", |time_zone| time_zone.name()))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.time_zone, None, "Local");
+                                    for time_zone in chrono_tz::TZ_VARIANTS {
+                                        ui.selectable_value(&mut self.time_zone, Some(time_zone), time_zone.name());
+                                    }
+                                });
+                            ui.add_enabled_ui(self.time_zone.is_some(), |ui| {
+                                ui.horizontal(|ui| {
+                                    for mode in TravelMode::ALL {
+                                        ui.radio_value(&mut self.settings.travel_mode, mode, mode.label());
+                                    }
+                                });
+                            });
+                            self.update_home_zone();
+
+                            ui.label("Clock:");
+                            ui.horizontal(|ui| {
+                                for hour_cycle in HourCycle::ALL {
+                                    if ui.radio_value(&mut self.settings.hour_cycle, hour_cycle, hour_cycle.label()).clicked() {
+                                        self.time_format = TimeFormat::new(hour_cycle);
+                                    }
+                                }
+                            });
+
+                            ui.label("Timeline:");
+                            ui.horizontal(|ui| {
+                                ui.label("Day starts at");
+                                egui::ComboBox::from_id_salt("day_start")
+                                    .width(64.0)
+                                    .selected_text(self.time_format.hour(self.settings.day_start_hour as u32))
+                                    .show_ui(ui, |ui| {
+                                        for hour in 0..=23 {
+                                            ui.selectable_value(&mut self.settings.day_start_hour, hour, self.time_format.hour(hour as u32));
+                                        }
+                                    });
+                            });
+                            let day_start = SimpleTime::new(self.settings.day_start_hour, 0, 0);
+                            if day_start != self.clock.day_start {
+                                // Today's plan day may now be another one
+                                self.clock.day_start = day_start;
+                                self.restart_clock();
+                            }
+                            let mut some_hours = self.settings.visible_hours.is_some();
+                            if ui.checkbox(&mut some_hours, "Only show some hours").changed() {
+                                let day_start = self.settings.day_start_hour;
+                                self.settings.visible_hours = some_hours.then_some((day_start, day_start));
+                            }
+                            if let Some(visible_hours) = &mut self.settings.visible_hours {
+                                visible_hours_picker(ui, visible_hours, self.settings.day_start_hour, &self.time_format);
+                            }
+
+                            ui.label("Time entry:");
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut self.settings.time_entry.typed, true, "Type");
+                                ui.radio_value(&mut self.settings.time_entry.typed, false, "Pick from lists");
+                                ui.checkbox(&mut self.settings.time_entry.seconds, "Seconds");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Snap to");
+                                for minutes in time_input::SNAP_CHOICES {
+                                    ui.radio_value(&mut self.settings.time_entry.snap_minutes, minutes, self.time_format.duration(minutes * 60));
+                                }
+                            });
+
+                            ui.label("Notifications:");
+                            ui.horizontal(|ui| {
+                                for backend in NotificationBackend::ALL {
+                                    if ui.radio_value(&mut self.settings.notification_backend, backend, backend.label()).clicked() {
+                                        self.notifier = backend.notifier(&self.toasts, &self.action_sender);
+                                    }
+                                }
+                            });
+
+                            ui.label("Quiet hours:");
+                            let mut removed_window = None;
+                            for (index, window) in self.settings.quiet_hours.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.vertical(|ui| {
+                                        time_picker(ui, &mut window.start_time, &format!("quiet_hours_{}_start", index), &self.time_format, &self.settings.time_entry);
+                                        time_picker(ui, &mut window.end_time, &format!("quiet_hours_{}_end", index), &self.time_format, &self.settings.time_entry);
+                                    });
+                                    if ui.small_button("✖").clicked() {
+                                        removed_window = Some(index);
+                                    }
+                                });
+                            }
+                            if let Some(index) = removed_window {
+                                self.settings.quiet_hours.remove(index);
+                            }
+                            if ui.small_button("Add quiet hours").clicked() {
+                                self.settings.quiet_hours.push(QuietHours::default());
+                            }
+                            ui.horizontal(|ui| {
+                                for mode in QuietMode::ALL {
+                                    ui.radio_value(&mut self.settings.quiet_mode, mode, mode.label());
+                                }
+                            });
+                            ui.checkbox(&mut self.settings.summarize_quiet_hours, "Summary when quiet hours end");
+
+                            ui.label("Default reminders before start / end:");
+                            ui.horizontal(|ui| {
+                                activity_form::reminder_picker(ui, &mut self.settings.remind_before_start, "settings_remind_start", &self.time_format);
+                                activity_form::reminder_picker(ui, &mut self.settings.remind_before_end, "settings_remind_end", &self.time_format);
+                            });
+
+                            ui.label("Daily digests:");
+                            let time_entry = self.settings.time_entry.clone();
+                            for kind in DigestKind::ALL {
+                                let time = self.settings.digest_time_mut(kind);
+                                let mut enabled = time.is_some();
+                                if ui.checkbox(&mut enabled, kind.label()).changed() {
+                                    *time = enabled.then(|| kind.default_time());
+                                }
+                                if let Some(time) = time {
+                                    time_picker(ui, time, &format!("digest_{:?}", kind), &self.time_format, &time_entry);
+                                }
+                            }
+
+                            ui.label("Holidays:");
+                            let mut holidays_changed = false;
+                            let mut removed_calendar = None;
+                            for (index, path) in self.settings.holiday_calendars.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                    ui.label(name).on_hover_text(path.display().to_string());
+                                    if ui.small_button("✖").clicked() {
+                                        removed_calendar = Some(index);
+                                    }
+                                });
+                            }
+                            if let Some(index) = removed_calendar {
+                                self.settings.holiday_calendars.remove(index);
+                                holidays_changed = true;
+                            }
+                            if ui.small_button("Add calendar…").clicked() {
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("Calendar", &["ics", "txt"])
+                                    .pick_file()
+                                {
+                                    self.settings.holiday_calendars.push(path);
+                                    holidays_changed = true;
+                                }
+                            }
+                            let today = self.clock.today();
+                            let mut day_off = self.settings.days_off.contains(&today);
+                            if ui.checkbox(&mut day_off, "Today is a day off").changed() {
+                                if day_off {
+                                    self.settings.days_off.insert(today);
+                                } else {
+                                    self.settings.days_off.remove(&today);
+                                }
+                                holidays_changed = true;
+                            }
+                            let mut removed_day_off = None;
+                            for date in self.settings.days_off.iter().filter(|date| **date != today) {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("Day off on {}", self.time_format.date(*date)));
+                                    if ui.small_button("✖").clicked() {
+                                        removed_day_off = Some(*date);
+                                    }
+                                });
+                            }
+                            if let Some(date) = removed_day_off {
+                                self.settings.days_off.remove(&date);
+                                holidays_changed = true;
+                            }
+                            let new_days_off = days_off_range(&self.days_off_from, &self.days_off_to);
+                            ui.horizontal(|ui| {
+                                ui.label("Days off from");
+                                ui.add(egui::TextEdit::singleline(&mut self.days_off_from).hint_text("YYYY-MM-DD").desired_width(80.0));
+                                ui.label("to");
+                                ui.add(egui::TextEdit::singleline(&mut self.days_off_to).hint_text("YYYY-MM-DD").desired_width(80.0));
+                                let add = ui
+                                    .add_enabled(new_days_off.is_some(), egui::Button::new("Add days off").small())
+                                    .on_disabled_hover_text("Type a date, or a range of up to a year");
+                                if let (true, Some(dates)) = (add.clicked(), new_days_off) {
+                                    self.settings.days_off.extend(dates);
+                                    self.days_off_from.clear();
+                                    self.days_off_to.clear();
+                                    holidays_changed = true;
+                                }
+                            });
+                            if holidays_changed {
+                                self.holidays = Holidays::load(&self.settings.holiday_calendars, &self.settings.days_off);
+                                self.update_activities();
+                            }
+                            if let Some(holiday) = self.holidays.name(today) {
+                                ui.label(egui::RichText::new(format!("Today: {}", holiday)).color(LIGHT_GREEN));
+                            }
+
+                            ui.separator();
+                            egui::CollapsingHeader::new("Notification text").show(ui, |ui| {
+                                ui.label(egui::RichText::new(templates::PLACEHOLDERS.join(" ")).color(LIGHT_GREY));
+                                egui::ComboBox::from_id_salt("template_category")
+                                    .selected_text(if self.template_category.is_empty() { "All activities" } else { &self.template_category })
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut self.template_category, "".to_string(), "All activities");
+                                        for category in self.categories() {
+                                            ui.selectable_value(&mut self.template_category, category.clone(), category);
+                                        }
+                                    });
+
+                                // Previewed on the first activity in the scope, or on an example
+                                let mut example = Activity::new(0, "DEEP WORK".to_string(), SimpleTime::new(9, 0, 0), SimpleTime::new(10, 30, 0));
+                                example.notes = "Draft the report".to_string();
+                                example.category = self.template_category.clone();
+                                let preview_activity = self.activities
+                                    .iter()
+                                    .find(|activity| self.template_category.is_empty() || activity.category() == self.template_category)
+                                    .unwrap_or(&example);
+
+                                let plan_day = PlanDay {
+                                    date: self.clock.today(),
+                                    day_start: self.clock.day_start(),
+                                    holidays: &self.holidays,
+                                };
+                                let templates = &mut self.settings.templates;
+                                for kind in TemplateKind::ALL {
+                                    ui.label(kind.label());
+                                    let mut template = templates.template(kind, &self.template_category);
+                                    let summary_changed = ui.text_edit_singleline(&mut template.summary).changed();
+                                    let body_changed = ui.text_edit_singleline(&mut template.body).changed();
+                                    if summary_changed || body_changed {
+                                        templates.scope_mut(&self.template_category).insert(kind, template.clone());
+                                    }
+                                    let preview = templates.notification(kind, preview_activity, &self.activities, &plan_day, 5, &self.clock.local_times(self.time_format));
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(format!("{}: {}", preview.summary, preview.body)).color(LIGHT_GREEN));
+                                        if templates.is_customized(kind, &self.template_category) && ui.small_button("Reset").clicked() {
+                                            templates.scope_mut(&self.template_category).remove(&kind);
+                                        }
+                                    });
+                                }
+                                templates.categories.retain(|_, category_templates| !category_templates.is_empty());
+                            });
+
+                            ui.separator();
+                            if ui.checkbox(&mut self.settings.sounds.enabled, "Sound alerts").changed() {
+                                self.sound_player = self.settings.sounds.player();
+                            }
+                            ui.add_enabled_ui(self.settings.sounds.enabled, |ui| {
+                                ui.add(egui::Slider::new(&mut self.settings.sounds.volume, 0.0..=1.0).text("Volume"));
+
+                                let mut preview = None;
+                                for event in NotificationEvent::ALL {
+                                    let file = self.settings.sounds.event_file_mut(event);
+                                    if let Some(source) = sound_file_picker(ui, event.label(), file, event) {
+                                        preview = Some(source);
+                                    }
+                                }
+
+                                let categories = self.categories();
+                                if !categories.is_empty() {
+                                    ui.label("Category sounds:");
+                                }
+                                for category in categories {
+                                    let mut file = self.settings.sounds.categories.get(&category).cloned();
+                                    if let Some(source) = sound_file_picker(ui, &category, &mut file, NotificationEvent::Start) {
+                                        preview = Some(source);
+                                    }
+                                    match file {
+                                        Some(file) => self.settings.sounds.categories.insert(category, file),
+                                        None => self.settings.sounds.categories.remove(&category),
+                                    };
+                                }
+
+                                if let Some(source) = preview {
+                                    self.play_sound(&source);
+                                }
+                            });
+
+                            ui.separator();
+                            egui::CollapsingHeader::new("Simulate time").show(ui, |ui| {
+                                ui.label("Start at:");
+                                time_picker(ui, &mut self.simulation_start, "simulation_start", &self.time_format, &self.settings.time_entry);
+                                ui.horizontal(|ui| {
+                                    for speed in clock::SIMULATION_SPEEDS {
+                                        ui.radio_value(&mut self.simulation_speed, speed, format!("{}×", speed));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    if ui.button("Start").clicked() {
+                                        let start = self.clock.now().date().and_time(
+                                            chrono::NaiveTime::from_num_seconds_from_midnight_opt(self.simulation_start.as_seconds(), 0)
+                                                .unwrap_or_default(),
+                                        );
+                                        self.set_clock(Box::new(SimulatedClock::new(start, self.simulation_speed)));
+                                    }
+                                    if self.clock.is_simulated() {
+                                        if ui.button("Stop").clicked() {
+                                            self.set_clock(Box::new(SystemClock));
+                                        }
+                                        ui.label(egui::RichText::new(self.time_format.wall_time(&self.clock.now())).color(RED));
+                                    }
+                                });
+                            });
+
+                            ui.add_space(5.0);
+
+                            // Settings that couldn't be read are only saved over when asked to
+                            if let Some(error) = &self.unreadable_settings {
+                                ui.label(egui::RichText::new(format!("The saved settings couldn't be read:\n{}", error)).color(RED));
+                                ui.label("Closing leaves them as they are, saving replaces them with these.");
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                if ui.button("Close").clicked() {
+                                    if self.unreadable_settings.is_none() {
+                                        self.save_settings();
+                                    }
+                                    self.settings_window_open = false;
+                                }
+                                if self.unreadable_settings.is_some() && ui.button("Save").clicked() {
+                                    self.save_settings();
+                                    self.settings_window_open = false;
+                                }
+                            });
+                        });
+                    });
+                if !settings_window_open {
+                    self.settings_window_open = false;
+                }
+            }
+
+            if self.paste_plan_window_open {
+                let mut paste_plan_window_open = true;
+                egui::Window::new("Paste Plan")
+                    .default_size(egui::vec2(140.0, 70.0))
+                    .title_bar(false)
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut paste_plan_window_open)
+                    .show(ui.ctx(), |ui| {
+                        ui.label("One activity per line, like \"09:00-10:30 Deep work\":");
+                        ui.add(egui::TextEdit::multiline(&mut self.paste_plan_text).desired_rows(6));
+
+                        let parsed_lines = text_plan::parse_text(&self.paste_plan_text, &self.clock.day_start());
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            ui.spacing_mut().item_spacing.y = 2.0;
+                            for line in &parsed_lines {
+                                match &line.result {
+                                    Ok((start_time, end_time, name)) => ui.label(
+                                        egui::RichText::new(format!(
+                                            "{}-{} {}",
+                                            self.time_format.time(start_time),
+                                            self.time_format.time(end_time),
+                                            name.to_ascii_uppercase(),
+                                        ))
+                                            .color(LIGHT_GREEN),
+                                    ),
+                                    Err(error) => ui.label(
+                                        egui::RichText::new(format!("{} ({})", line.text, error))
+                                            .color(RED),
+                                    ),
+                                };
+                            }
+                        });
+
+                        ui.checkbox(&mut self.paste_plan_replace, "Replace current plan");
+
+                        ui.add_space(5.0);
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            let valid_lines: Vec<_> = parsed_lines
+                                .iter()
+                                .filter_map(|line| line.result.as_ref().ok())
+                                .collect();
+                            let add_label = format!("Add {}", valid_lines.len());
+                            if ui.add_enabled(!valid_lines.is_empty(), egui::Button::new(add_label)).clicked() {
+                                if self.paste_plan_replace {
+                                    self.activities.clear();
+                                }
+                                for (start_time, end_time, name) in valid_lines {
+                                    let id = self.next_activity_id();
+                                    self.activities.push(Activity::new(id, name.to_ascii_uppercase(), *start_time, *end_time));
+                                }
+                                self.update_activities();
+                                self.paste_plan_text = "".to_string();
+                                self.paste_plan_window_open = false;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.paste_plan_window_open = false;
+                            }
+                        });
+                    });
+                if !paste_plan_window_open {
+                    self.paste_plan_window_open = false;
+                }
+            }
+
+            if self.quick_add_window_open {
+                let mut quick_add_window_open = true;
+                egui::Window::new("Quick Add")
+                    .default_size(egui::vec2(140.0, 70.0))
+                    .title_bar(false)
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut quick_add_window_open)
+                    .show(ui.ctx(), |ui| {
+                        if let Some(home) = self.clock.home {
+                            ui.label(format!("Times in {}", home.name()));
+                        }
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.quick_add_text)
+                                .hint_text("gym 18:00 for 1h every weekday"),
+                        );
+                        response.request_focus();
+                        let entered = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+                        // Show how the phrase is understood before adding it
+                        let parsed = quick_add::parse(&self.quick_add_text, self.clock.today(), &self.clock.day_start(), &self.time_format);
+                        if !self.quick_add_text.trim().is_empty() {
+                            ui.spacing_mut().item_spacing.y = 2.0;
+                            match &parsed {
+                                Ok(quick_add) => {
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "{}-{} {}",
+                                            self.time_format.time(&quick_add.start_time),
+                                            self.time_format.time(&quick_add.end_time),
+                                            quick_add.name.to_ascii_uppercase(),
+                                        ))
+                                            .color(LIGHT_GREEN),
+                                    );
+                                    ui.label(egui::RichText::new(self.time_format.days(&quick_add.days)).color(LIGHT_GREEN));
+                                    if quick_add.assumed_end {
+                                        ui.label(egui::RichText::new("No end or duration, so it lasts 1 h").color(GREY));
+                                    }
+                                    if !quick_add.ignored.is_empty() {
+                                        ui.label(
+                                            egui::RichText::new(format!("Didn't understand: {}", quick_add.ignored.join(" ")))
+                                                .color(RED),
+                                        );
+                                    }
+                                }
+                                Err(error) => {
+                                    ui.label(egui::RichText::new(error).color(RED));
+                                }
+                            }
+                        }
+
+                        ui.add_space(5.0);
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            let add_clicked = ui.add_enabled(parsed.is_ok(), egui::Button::new("Add")).clicked();
+                            if let (true, Ok(quick_add)) = (add_clicked || entered, &parsed) {
+                                let id = self.next_activity_id();
+                                self.activities.push(quick_add.to_activity(id));
+                                self.update_activities();
+                                self.quick_add_text = "".to_string();
+                                self.quick_add_window_open = false;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.quick_add_window_open = false;
+                            }
+                        });
+                    });
+                if !quick_add_window_open {
+                    self.quick_add_window_open = false;
+                }
+            }
+
+            // What's running and what's next, also shown in the window title for the taskbar
+            let status = Status::new(&self.activities, &self.clock, &self.holidays, &self.history);
+            let status_rect = egui::Rect::from_min_size(ui.cursor().min, egui::vec2(ui.available_width(), STATUS_HEIGHT));
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(status_rect), |ui| {
+                status_panel(ui, &status, &self.time_format);
             });
+            // The panel keeps its height whatever it shows, so the timeline below doesn't jump
+            ui.advance_cursor_after_rect(status_rect);
+            let title = status.title(&self.time_format);
+            if title != self.window_title {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+                self.window_title = title;
+            }
 
-            // Draw the activities
-            for activity in &self.activities {
-                let activity_color = if activity.is_now { LIGHT_GREEN } else { LIGHT_GREY };
+            // First, draw the hours as rows of labels, with the local time
+            let today = self.clock.today();
+            let now = self.clock.now_utc();
+            let timeline = Timeline::new(&self.clock, today, self.settings.visible_hours);
+            let hour_height = timeline.hour_height();
+            // Placed below whatever was drawn above, so it follows the buttons, the status and the font size
+            let top = ui.cursor().top();
+            let left = ui.cursor().left();
+            for hour_start in timeline.hour_starts() {
+                let rect = egui::Rect::from_min_size(
+                    egui::pos2(left, top + timeline.y(hour_start)),
+                    egui::vec2(100.0, hour_height),
+                );
+                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+                    let label = self.time_format.wall_time(&hour_start.with_timezone(&chrono::Local));
+                    if hour_start <= now && now < hour_start + chrono::Duration::hours(1) {
+                        ui.label(
+                            egui::RichText::new(label)
+                                .size(20.0)
+                                .color(WHITE),
+                        );
+                    } else {
+                        ui.label(
+                            egui::RichText::new(label)
+                                .size(20.0)
+                                .color(GREY),
+                        );
+                    }
+                });
+            }
+
+            // Draw the activities, leaving out those skipped by the clocks going forward
+            // and cutting them to the hours shown
+            for activity in self.activities.iter().filter(|activity| activity.occurs_on(today, &self.holidays)) {
+                let Some((start, end)) = activity.span(&self.clock, today, &self.history) else {
+                    continue;
+                };
+                let (start_y, end_y) = (top + timeline.y(start), top + timeline.y(end));
+                if end_y <= start_y {
+                    continue;
+                }
+                let activity_color = if activity.is_now { LIGHT_GREEN } else { category_color(activity.category()) };
                 let activity_font_color = if activity.is_now { DARK_GREEN } else { WHITE };
 
-                let fixed_pos = egui::pos2(
-                    65.0,
-                    73.0 + 33.0 * (activity.start_time.hour() as f32 + activity.start_time.minute() as f32 / 60.0),
-                );
-                let fixed_size = egui::vec2(
-                    200.0,
-                    33.0 * (activity.end_time.hour() as f32 + activity.end_time.minute() as f32 / 60.0)
-                        - 33.0 * (activity.start_time.hour() as f32 + activity.start_time.minute() as f32 / 60.0),
-                );
+                let fixed_pos = egui::pos2(65.0, start_y);
+                let fixed_size = egui::vec2(200.0, end_y - start_y);
 
                 let rect = egui::Rect::from_min_size(fixed_pos, fixed_size);
 
-                ui.allocate_ui_at_rect(rect, |ui| {
+                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
                     ui.painter().rect_filled(rect, 3.0, activity_color);
                     ui.with_layout(egui::Layout::centered_and_justified(egui::Direction::TopDown), |ui| {
+                        let label = if self.history.is_done(today, activity.id) {
+                            format!("✔ {}", activity.name)
+                        } else {
+                            activity.name.clone()
+                        };
                         if ui.add(egui::Label::new(
-                            egui::RichText::new(&activity.name)
+                            egui::RichText::new(label)
                                 .color(activity_font_color)
                         ).sense(egui::Sense::click())).clicked() {
                             self.selected_activity_id_for_update = Some(activity.id);
-                            self.selected_activity_new_name = activity.name().to_string();
-                            self.selected_activity_new_start_time = (activity.start_time().hour(), activity.start_time().minute());
-                            self.selected_activity_new_end_time = (activity.end_time().hour(), activity.end_time().minute());
+                            self.selected_activity_form = ActivityForm::from_activity(activity);
                             self.update_activity_window_open = true;
                         }
                     });
                 });
             }
 
-            // Draw a horizontal line that marks the current time
-            let current_time = SimpleTime::from_now();
-            let current_time_y = 73.0 + 33.0 * (current_time.hour() as f32 + current_time.minute() as f32 / 60.0);
-            ui.allocate_ui_with_layout(
-                egui::vec2(263.0, 2.0),
-                egui::Layout::top_down(egui::Align::Min),
-                |ui| {
-                    ui.painter().line_segment(
-                        [egui::pos2(0.0, current_time_y), egui::pos2(255.0, current_time_y)],
-                        (1.0, RED),
-                    );
-                    ui.painter().line_segment(
-                        [egui::pos2(255.0, current_time_y), egui::pos2(264.0, current_time_y)],
-                        (3.0, RED),
-                    );
-                },
-            );
+            // Draw a horizontal line that marks the current time, when it's within the hours shown
+            if timeline.contains(now) {
+                let current_time_y = top + timeline.y(now);
+                ui.allocate_ui_with_layout(
+                    egui::vec2(263.0, 2.0),
+                    egui::Layout::top_down(egui::Align::Min),
+                    |ui| {
+                        ui.painter().line_segment(
+                            [egui::pos2(0.0, current_time_y), egui::pos2(255.0, current_time_y)],
+                            (1.0, RED),
+                        );
+                        ui.painter().line_segment(
+                            [egui::pos2(255.0, current_time_y), egui::pos2(264.0, current_time_y)],
+                            (3.0, RED),
+                        );
+                    },
+                );
+            }
+
+            // In-app notifications, newest at the bottom
+            let toasts = self.toasts.toasts();
+            let mut picked_action = None;
+            if !toasts.is_empty() {
+                egui::Area::new(egui::Id::new("toasts"))
+                    .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
+                    .order(egui::Order::Foreground)
+                    .show(ui.ctx(), |ui| {
+                        for (index, toast) in toasts.iter().enumerate() {
+                            egui::Frame::popup(ui.style())
+                                .fill(GREY)
+                                .show(ui, |ui| {
+                                    ui.set_width(230.0);
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(&toast.notification.summary).strong().color(WHITE));
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                            if ui.small_button("✖").clicked() {
+                                                self.toasts.dismiss(index);
+                                            }
+                                        });
+                                    });
+                                    ui.label(egui::RichText::new(&toast.notification.body).color(WHITE));
+                                    ui.horizontal_wrapped(|ui| {
+                                        for action in &toast.notification.actions {
+                                            if ui.small_button(action.label()).clicked() {
+                                                picked_action = Some((index, *action));
+                                            }
+                                        }
+                                    });
+                                });
+                        }
+                    });
+            }
+            if let Some((index, action)) = picked_action {
+                self.toasts.dismiss(index);
+                self.handle_action(ActionEvent { action, notification: toasts[index].notification.clone() });
+            }
 
         });
+
+        // The plan is checked when a wakeup comes due, and after input or actions that may have
+        // changed it. Moving the pointer around, or a repaint for the status, doesn't check it.
+        let has_input = ctx.input(|input| {
+            input.pointer.any_down()
+                || input.events.iter().any(|event| !matches!(event, egui::Event::PointerMoved(_) | egui::Event::MouseMoved(_) | egui::Event::PointerGone))
+        });
+        if is_due || has_input || std::mem::take(&mut self.needs_check) {
+            if self.update_activities_with_notifications() {
+                ctx.request_repaint();
+            }
+            self.schedule_wakeup();
+        }
+        self.request_wakeup_repaint(ctx);
+    }
+}
+
+/// The current activity with a progress bar, and the next one
+fn status_panel(ui: &mut egui::Ui, status: &Status, format: &TimeFormat) {
+    ui.spacing_mut().item_spacing.y = 4.0;
+    match &status.current {
+        Some(current) => {
+            ui.add(
+                egui::ProgressBar::new(current.progress)
+                    .fill(DARK_GREEN)
+                    .text(egui::RichText::new(format!("{} · {}", current.name, status::left(current, format))).color(WHITE)),
+            );
+        }
+        None => {
+            ui.label(egui::RichText::new("Nothing right now").color(GREY));
+        }
+    }
+    match &status.next {
+        Some(next) => ui.label(egui::RichText::new(format!("Next: {} {}", next.name, status::starts_in(next, format))).color(LIGHT_GREY)),
+        None => ui.label(egui::RichText::new("Nothing next").color(GREY)),
+    };
+}
+
+/// Loads the saved history. One that can't be read is set aside, so it isn't saved over,
+/// and an empty history is used along with why.
+fn load_history() -> (History, Option<String>) {
+    match History::load() {
+        Ok(history) => (history, None),
+        Err(error) => {
+            let kept = match History::set_aside() {
+                Ok(path) => format!("It was kept as {}", path),
+                Err(error) => format!("It couldn't be kept aside: {}", error),
+            };
+            (History::default(), Some(format!("{}: {}\n{}", History::path(), error, kept)))
+        }
+    }
+}
+
+/// The time zone the app keeps to for a plan's `time_zone`, if any
+fn home_zone(time_zone: Option<Tz>, travel_mode: TravelMode) -> Option<Tz> {
+    match travel_mode {
+        TravelMode::StayAnchored => time_zone,
+        TravelMode::FollowMe => None,
     }
 }
 
-fn time_picker(ui: &mut egui::Ui, time: &mut (u8, u8), id_prefix: &str) {
+/// Shows the chosen sound file with buttons to change, reset and preview it.
+/// Returns the sound to preview when asked, which is the `bundled` one while no file is set.
+fn sound_file_picker(ui: &mut egui::Ui, label: &str, file: &mut Option<std::path::PathBuf>, bundled: NotificationEvent) -> Option<SoundSource> {
+    let mut preview = None;
+    ui.horizontal(|ui| {
+        let file_name = file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map_or("Default".to_string(), |name| name.to_string_lossy().to_string());
+        ui.label(format!("{}: {}", label, file_name));
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
-            ui.add_space(20.0);
-
-            egui::ComboBox::from_id_salt(format!("{}_minute", id_prefix))
-                .width(64.0)
-                .selected_text(
-                    format!("{} min", time.1)
-                )
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut time.1, 0, "Minute".to_string());
-                    for m in 0..=59 {
-                        ui.selectable_value(&mut time.1, m, m.to_string());
-                    }
+            if ui.small_button("▶").clicked() {
+                preview = Some(match file {
+                    Some(path) => SoundSource::File(path.clone()),
+                    None => SoundSource::Bundled(bundled),
                 });
+            }
+            if file.is_some() && ui.small_button("Reset").clicked() {
+                *file = None;
+            }
+            if ui.small_button("…").clicked() {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("Sound", &["wav", "ogg", "mp3", "flac"])
+                    .pick_file()
+                {
+                    *file = Some(path);
+                }
+            }
+        });
+    });
+    preview
+}
 
-            egui::ComboBox::from_id_salt(format!("{}_hour", id_prefix))
-                .width(64.0)
-                .selected_text(
-                    format!("{} hs", time.0)
-                )
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut time.0, 0, "Hour".to_string());
-                    for h in 0..=23 {
-                        ui.selectable_value(&mut time.0, h, h.to_string());
-                    }
-                });
+/// Picks the first and last hour shown on the timeline, listed from the day start on
+fn visible_hours_picker(ui: &mut egui::Ui, visible_hours: &mut (u8, u8), day_start: u8, format: &TimeFormat) {
+    let hour_at = |position: u8| (day_start + position) % 24;
+    let position = |hour: u8| (hour + 24 - day_start) % 24;
+    let (mut first, mut last) = (position(visible_hours.0), match position(visible_hours.1) {
+        0 => 24,
+        last => last,
+    });
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+        ui.add_space(20.0);
+
+        egui::ComboBox::from_id_salt("visible_hours_last")
+            .width(64.0)
+            .selected_text(format!("to {}", format.hour(hour_at(last) as u32)))
+            .show_ui(ui, |ui| {
+                for position in (first + 1)..=24 {
+                    ui.selectable_value(&mut last, position, format.hour(hour_at(position) as u32));
+                }
+            });
+
+        egui::ComboBox::from_id_salt("visible_hours_first")
+            .width(64.0)
+            .selected_text(format.hour(hour_at(first) as u32))
+            .show_ui(ui, |ui| {
+                for position in 0..=23 {
+                    ui.selectable_value(&mut first, position, format.hour(hour_at(position) as u32));
+                }
+            });
+    });
+    last = last.max(first + 1);
+    *visible_hours = (hour_at(first), hour_at(last));
+}
+
+/// Picks a range of hours counted from the day start, shown as the times of day they fall on
+fn hour_range_picker(ui: &mut egui::Ui, first_hour: &mut u8, last_hour: &mut u8, day_start: u8, id_prefix: &str, format: &TimeFormat) {
+    let label = |hour: u8| format.hour(printable::hour_of_day(day_start, hour as u32));
+    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+        ui.add_space(20.0);
+
+        egui::ComboBox::from_id_salt(format!("{}_last", id_prefix))
+            .width(64.0)
+            .selected_text(format!("to {}", label(*last_hour)))
+            .show_ui(ui, |ui| {
+                for h in (*first_hour + 1)..=24 {
+                    ui.selectable_value(last_hour, h, label(h));
+                }
+            });
 
+        egui::ComboBox::from_id_salt(format!("{}_first", id_prefix))
+            .width(64.0)
+            .selected_text(label(*first_hour))
+            .show_ui(ui, |ui| {
+                for h in 0..=23 {
+                    ui.selectable_value(first_hour, h, label(h));
+                }
+            });
     });
+
+    if *last_hour <= *first_hour {
+        *last_hour = *first_hour + 1;
+    }
 }